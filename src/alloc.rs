@@ -0,0 +1,315 @@
+use crate::fixed_buf::{fixed_buf_api, FixedBuf};
+use crate::{CFixedStringN, Error};
+use std::alloc::Layout;
+use std::ptr::{self, NonNull};
+use std::slice;
+
+/// An allocator for the heap fallback of `CFixedStringIn`, such as a frame
+/// or arena allocator.
+///
+/// # Safety
+///
+/// `allocate` must return memory valid for `layout` that stays valid until
+/// it is passed to `deallocate` with the same layout, or until the
+/// allocator itself goes away.
+pub unsafe trait Alloc {
+    /// Allocates memory for `layout`, returning `None` on failure
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>>;
+
+    /// Frees memory returned by `allocate`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from `allocate` on this allocator with `layout`.
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+}
+
+/// The global allocator, as used by `CFixedString`.
+///
+/// Zero-sized layouts don't reach the global allocator, they get a dangling
+/// pointer aligned for the layout and freeing them does nothing.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Global;
+
+unsafe impl Alloc for Global {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        if layout.size() == 0 {
+            // The alignment is a non-zero power of two, so this is a valid
+            // dangling pointer for the layout
+            return NonNull::new(ptr::null_mut::<u8>().wrapping_add(layout.align()));
+        }
+
+        NonNull::new(unsafe { std::alloc::alloc(layout) })
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() != 0 {
+            std::alloc::dealloc(ptr.as_ptr(), layout)
+        }
+    }
+}
+
+unsafe impl<A: Alloc + ?Sized> Alloc for &A {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        (**self).allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        (**self).deallocate(ptr, layout)
+    }
+}
+
+/// Allocates from the arena. Freeing is a no-op, the memory comes back when
+/// the arena is reset, which the borrow checker only allows once the strings
+/// using it are gone.
+#[cfg(feature = "bumpalo")]
+unsafe impl Alloc for bumpalo::Bump {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        self.try_alloc_layout(layout).ok()
+    }
+
+    unsafe fn deallocate(&self, _ptr: NonNull<u8>, _layout: Layout) {}
+}
+
+/// Adapts an allocator from the unstable `allocator_api` to `Alloc`.
+#[cfg(feature = "allocator_api")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AllocatorApi<A>(pub A);
+
+#[cfg(feature = "allocator_api")]
+unsafe impl<A: core::alloc::Allocator> Alloc for AllocatorApi<A> {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        self.0.allocate(layout).ok().map(|p| p.cast())
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.0.deallocate(ptr, layout)
+    }
+}
+
+enum Repr<const N: usize> {
+    Local {
+        s: [u8; N],
+        len: usize,
+    },
+    /// The whole buffer is initialized so it can be used as a slice
+    Heap {
+        ptr: NonNull<u8>,
+        cap: usize,
+        len: usize,
+    },
+}
+
+/// A `CFixedString` whose heap fallback comes from `A` instead of the
+/// global heap, created with `CFixedStringN::new_in`.
+///
+/// Zero bytes are handled by `NulPolicy` like for `CFixedString`, but heap
+/// growth is not counted against `set_heap_budget` as the memory doesn't
+/// come from the global heap.
+pub struct CFixedStringIn<A: Alloc, const N: usize> {
+    alloc: A,
+    repr: Repr<N>,
+}
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Creates an empty string that moves to memory from `alloc` when it
+    /// outgrows the inline buffer
+    pub fn new_in<A: Alloc>(alloc: A) -> CFixedStringIn<A, N> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::HAS_TERMINATOR;
+
+        CFixedStringIn {
+            alloc,
+            repr: Repr::Local { s: [0; N], len: 0 },
+        }
+    }
+}
+
+impl<A: Alloc, const N: usize> CFixedStringIn<A, N> {
+    /// Appends `s`, moving to the heap if it doesn't fit inline
+    pub fn push_str(&mut self, s: &str) -> Result<(), Error> {
+        self.push(s, false)
+    }
+
+    /// Returns true if the string has moved to memory from the allocator
+    pub fn is_allocated(&self) -> bool {
+        matches!(self.repr, Repr::Heap { .. })
+    }
+
+    /// Returns the allocator
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+}
+
+impl<A: Alloc, const N: usize> FixedBuf for CFixedStringIn<A, N> {
+    fn buf(&self) -> &[u8] {
+        match self.repr {
+            Repr::Local { ref s, .. } => s,
+            Repr::Heap { ptr, cap, .. } => unsafe { slice::from_raw_parts(ptr.as_ptr(), cap) },
+        }
+    }
+
+    fn buf_mut(&mut self) -> &mut [u8] {
+        match self.repr {
+            Repr::Local { ref mut s, .. } => s,
+            Repr::Heap { ptr, cap, .. } => unsafe { slice::from_raw_parts_mut(ptr.as_ptr(), cap) },
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self.repr {
+            Repr::Local { len, .. } | Repr::Heap { len, .. } => len,
+        }
+    }
+
+    fn set_len(&mut self, new_len: usize) {
+        match self.repr {
+            Repr::Local { ref mut len, .. } | Repr::Heap { ref mut len, .. } => *len = new_len,
+        }
+    }
+
+    // Moves to a heap buffer of at least `needed` bytes, doubling so that
+    // repeated appends don't copy every time. Clearing keeps the buffer for
+    // reuse.
+    fn grow(&mut self, needed: usize) -> Result<(), Error> {
+        let old_cap = self.buf().len();
+        let cap = needed.max(old_cap * 2);
+        let layout = Layout::array::<u8>(cap).map_err(|_| Error::AllocationFailed { needed })?;
+        let new = self
+            .alloc
+            .allocate(layout)
+            .ok_or(Error::AllocationFailed { needed })?;
+
+        let len = self.len();
+
+        unsafe {
+            ptr::copy_nonoverlapping(self.buf().as_ptr(), new.as_ptr(), len + 1);
+            ptr::write_bytes(new.as_ptr().add(len + 1), 0, cap - len - 1);
+        }
+
+        if let Repr::Heap { ptr, cap, .. } = self.repr {
+            unsafe {
+                self.alloc
+                    .deallocate(ptr, Layout::array::<u8>(cap).unwrap())
+            };
+        }
+
+        self.repr = Repr::Heap { ptr: new, cap, len };
+        Ok(())
+    }
+}
+
+fixed_buf_api!(impl[A: Alloc, const N: usize] CFixedStringIn<A, N>);
+
+impl<A: Alloc, const N: usize> Drop for CFixedStringIn<A, N> {
+    fn drop(&mut self) {
+        if let Repr::Heap { ptr, cap, .. } = self.repr {
+            unsafe {
+                self.alloc
+                    .deallocate(ptr, Layout::array::<u8>(cap).unwrap())
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::fmt::Write;
+
+    // Counts what goes through it so the tests can see the heap path
+    #[derive(Default)]
+    struct Counting {
+        live: Cell<usize>,
+        total: Cell<usize>,
+    }
+
+    unsafe impl Alloc for Counting {
+        fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+            self.live.set(self.live.get() + 1);
+            self.total.set(self.total.get() + 1);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.live.set(self.live.get() - 1);
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    #[test]
+    fn test_new_in() {
+        let alloc = Counting::default();
+
+        {
+            let mut t = CFixedStringN::<8>::new_in(&alloc);
+            t.push_str("inline").unwrap();
+            assert!(!t.is_allocated());
+            assert_eq!(alloc.total.get(), 0);
+
+            for i in 0..100 {
+                write!(&mut t, "{}", i % 10).unwrap();
+            }
+
+            assert!(t.is_allocated());
+            assert_eq!(t.len(), 106);
+            assert!(t.as_str().starts_with("inline0123"));
+            assert_eq!(t.to_bytes_with_nul().last(), Some(&0));
+
+            // Doubling keeps the number of allocations small
+            assert!(alloc.total.get() <= 5);
+            assert_eq!(alloc.live.get(), 1);
+
+            assert_eq!(t.push_str("\0"), Err(Error::InteriorNul { position: 0 }));
+            t.clear();
+            assert_eq!(t.as_str(), "");
+        }
+
+        assert_eq!(alloc.live.get(), 0);
+    }
+
+    #[test]
+    fn test_global_zero_size() {
+        for layout in [Layout::new::<()>(), Layout::from_size_align(0, 64).unwrap()] {
+            let ptr = Global.allocate(layout).unwrap();
+            assert_eq!(ptr.as_ptr() as usize % layout.align(), 0);
+            unsafe { Global.deallocate(ptr, layout) };
+        }
+
+        let layout = Layout::new::<u64>();
+        let ptr = Global.allocate(layout).unwrap();
+        unsafe { Global.deallocate(ptr, layout) };
+    }
+
+    #[cfg(feature = "allocator_api")]
+    #[test]
+    fn test_allocator_api() {
+        let mut t = CFixedStringN::<4>::new_in(AllocatorApi(std::alloc::Global));
+        t.push_str("allocator").unwrap();
+        assert!(t.is_allocated());
+        assert_eq!(t.as_str(), "allocator");
+    }
+
+    #[cfg(feature = "bumpalo")]
+    #[test]
+    fn test_bumpalo() {
+        let mut bump = bumpalo::Bump::new();
+
+        for frame in 0..3 {
+            {
+                let mut t = crate::CFixedString::new_in(&bump);
+                let long = "b".repeat(crate::STRING_SIZE * 2);
+                t.push_str(&long).unwrap();
+                write!(&mut t, "{}", frame).unwrap();
+
+                assert!(t.is_allocated());
+                assert!(t.as_str().ends_with(&frame.to_string()));
+                assert!(bump.allocated_bytes() >= long.len());
+            }
+
+            bump.reset();
+        }
+    }
+}
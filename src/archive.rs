@@ -0,0 +1,70 @@
+use crate::CFixedStringN;
+use rkyv::rancor::{Fallible, Source};
+use rkyv::string::{ArchivedString, StringResolver};
+use rkyv::{Archive, Deserialize, Place, Serialize, SerializeUnsized};
+
+/// Archives as an `ArchivedString`, which is read back as a `&str` straight
+/// from the archive bytes. Invalid UTF-8 is replaced like `to_string` does.
+impl<const N: usize> Archive for CFixedStringN<N> {
+    type Archived = ArchivedString;
+    type Resolver = StringResolver;
+
+    fn resolve(&self, resolver: StringResolver, out: Place<ArchivedString>) {
+        ArchivedString::resolve_from_str(&self.to_string(), resolver, out);
+    }
+}
+
+impl<S: Fallible + ?Sized, const N: usize> Serialize<S> for CFixedStringN<N>
+where
+    S::Error: Source,
+    str: SerializeUnsized<S>,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<StringResolver, S::Error> {
+        ArchivedString::serialize_from_str(&self.to_string(), serializer)
+    }
+}
+
+/// Copies the archived string back, storing short values inline.
+///
+/// Zero characters are handled by `NulPolicy` like any other write.
+impl<D: Fallible + ?Sized, const N: usize> Deserialize<CFixedStringN<N>, D> for ArchivedString
+where
+    D::Error: Source,
+{
+    fn deserialize(&self, _: &mut D) -> Result<CFixedStringN<N>, D::Error> {
+        let mut t = CFixedStringN::new();
+        t.push_bytes(self.as_bytes()).map_err(D::Error::new)?;
+        Ok(t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CFixedString, STRING_SIZE};
+    use rkyv::rancor::Error;
+
+    #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+    struct Shader {
+        name: CFixedString,
+        entry: CFixedString,
+    }
+
+    #[test]
+    fn test_rkyv_round_trip() {
+        let long = "e".repeat(STRING_SIZE * 2);
+        let shader = Shader {
+            name: CFixedString::from_str("blur"),
+            entry: CFixedString::from_str(&long),
+        };
+
+        let bytes = rkyv::to_bytes::<Error>(&shader).unwrap();
+        let archived = rkyv::access::<ArchivedShader, Error>(&bytes).unwrap();
+        assert_eq!(archived.name.as_str(), "blur");
+        assert_eq!(archived.entry.as_str(), long);
+
+        let shader = rkyv::deserialize::<Shader, Error>(archived).unwrap();
+        assert!(!shader.name.is_allocated());
+        assert_eq!(shader.name, "blur");
+        assert_eq!(shader.entry, long);
+    }
+}
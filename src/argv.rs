@@ -0,0 +1,141 @@
+use crate::{CFixedString, Error};
+use core::ffi::{c_char, c_int};
+use std::ffi::OsStr;
+use std::ptr;
+
+/// An `argc`/`argv` pair for handing command-line arguments to C
+/// libraries, such as GTK, SDL or MPI, that want them at init.
+///
+/// Each argument is stored in its own `CFixedString`, so short arguments
+/// don't need a heap allocation each. The pointer array is terminated with
+/// a NULL entry like the one C programs get in `main`.
+pub struct CArgv {
+    args: Vec<CFixedString>,
+    ptrs: Vec<*const c_char>,
+}
+
+impl CArgv {
+    /// Builds the argument array from any sequence of `OsStr`s.
+    ///
+    /// Any invalid Unicode is replaced with `U+FFFD REPLACEMENT CHARACTER`,
+    /// as the arguments are also available as strings through `args`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `try_new` fails.
+    pub fn new<I, S>(args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        Self::try_new(args).unwrap()
+    }
+
+    /// Builds the argument array like `new`, failing if an argument
+    /// contains a zero byte rejected by `NulPolicy` or has to go on the
+    /// heap and the heap budget rejects it.
+    pub fn try_new<I, S>(args: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let args = args
+            .into_iter()
+            .map(|arg| {
+                let mut fixed = CFixedString::new();
+                fixed.push_bytes(arg.as_ref().to_string_lossy().as_bytes())?;
+                Ok(fixed)
+            })
+            .collect::<Result<Vec<CFixedString>, Error>>()?;
+
+        // The strings live in the `args` allocation which isn't touched
+        // after this point, so the pointers stay valid as `self` moves.
+        let ptrs = args
+            .iter()
+            .map(|arg| arg.as_ptr())
+            .chain(Some(ptr::null()))
+            .collect();
+
+        Ok(CArgv { args, ptrs })
+    }
+
+    /// Builds the argument array from the arguments of the current process
+    pub fn from_env() -> Self {
+        Self::new(std::env::args_os())
+    }
+
+    /// Returns the number of arguments, not counting the NULL terminator
+    pub fn argc(&self) -> c_int {
+        self.args.len() as c_int
+    }
+
+    /// Returns the NULL terminated argument array.
+    ///
+    /// The pointers are valid for as long as `self` is alive.
+    pub fn argv(&self) -> *const *const c_char {
+        self.ptrs.as_ptr()
+    }
+
+    /// Returns the arguments as `CFixedString`s
+    pub fn args(&self) -> &[CFixedString] {
+        &self.args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::ffi::CStr;
+
+    #[test]
+    fn test_argv() {
+        let long = "x".repeat(1000);
+        let argv = CArgv::new(["prog", "--flag", long.as_str()]);
+
+        // moving must not invalidate the pointers
+        let argv = Box::new(argv);
+
+        assert_eq!(argv.argc(), 3);
+
+        unsafe {
+            let ptrs = std::slice::from_raw_parts(argv.argv(), 4);
+
+            assert_eq!(CStr::from_ptr(ptrs[0]).to_bytes(), b"prog");
+            assert_eq!(CStr::from_ptr(ptrs[1]).to_bytes(), b"--flag");
+            assert_eq!(CStr::from_ptr(ptrs[2]).to_bytes(), long.as_bytes());
+            assert!(ptrs[3].is_null());
+        }
+    }
+
+    #[test]
+    fn test_from_env() {
+        let argv = CArgv::from_env();
+
+        assert_eq!(argv.argc() as usize, std::env::args_os().count());
+        assert!(argv.args()[0] == std::env::args_os().next().unwrap());
+    }
+
+    #[test]
+    fn test_try_new() {
+        let argv = CArgv::try_new(["app", "--flag"]).unwrap();
+        assert_eq!(argv.argc(), 2);
+
+        let err = CArgv::try_new(["app", "bad\0arg"])
+            .err()
+            .map(|e| e.to_string());
+        assert_eq!(
+            err.as_deref(),
+            Some("zero byte found in input at position 3")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_invalid_unicode() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let argv = CArgv::new([OsStr::from_bytes(b"app"), OsStr::from_bytes(b"a\xffb")]);
+        assert_eq!(argv.args()[1], "a\u{fffd}b");
+        assert!(std::str::from_utf8(argv.args()[1].to_bytes()).is_ok());
+    }
+}
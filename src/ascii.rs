@@ -0,0 +1,87 @@
+use std::borrow::Borrow;
+use std::hash::{Hash, Hasher};
+
+/// Wrapper that compares and hashes a string with ASCII case folding.
+///
+/// This allows header-like keys to be used in a `HashMap` without
+/// lowercasing them into new allocations first. Lookups with a plain `&str`
+/// go through `AsciiCaseInsensitive::new`:
+///
+/// ```
+/// use cfixed_string::{AsciiCaseInsensitive, CFixedString};
+/// use std::collections::HashMap;
+///
+/// let mut map = HashMap::new();
+/// map.insert(AsciiCaseInsensitive(CFixedString::from_str("Content-Type")), 1);
+///
+/// assert_eq!(map.get(AsciiCaseInsensitive::new("content-type")), Some(&1));
+/// ```
+#[derive(Clone, Copy, Debug)]
+#[repr(transparent)]
+pub struct AsciiCaseInsensitive<T: ?Sized>(pub T);
+
+impl AsciiCaseInsensitive<str> {
+    /// Wraps a borrowed `str` for lookups
+    pub fn new(s: &str) -> &Self {
+        // Fine because of `repr(transparent)`
+        unsafe { &*(s as *const str as *const Self) }
+    }
+}
+
+impl<T: ?Sized + AsRef<str>> AsciiCaseInsensitive<T> {
+    fn bytes(&self) -> &[u8] {
+        self.0.as_ref().as_bytes()
+    }
+}
+
+impl<A, B> PartialEq<AsciiCaseInsensitive<B>> for AsciiCaseInsensitive<A>
+where
+    A: ?Sized + AsRef<str>,
+    B: ?Sized + AsRef<str>,
+{
+    fn eq(&self, other: &AsciiCaseInsensitive<B>) -> bool {
+        self.bytes().eq_ignore_ascii_case(other.bytes())
+    }
+}
+
+impl<T: ?Sized + AsRef<str>> Eq for AsciiCaseInsensitive<T> {}
+
+impl<T: ?Sized + AsRef<str>> Hash for AsciiCaseInsensitive<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for &b in self.bytes() {
+            state.write_u8(b.to_ascii_lowercase());
+        }
+
+        // Same terminator as `str` uses, so "ab" + "c" and "a" + "bc"
+        // hash differently in tuples
+        state.write_u8(0xff);
+    }
+}
+
+impl<T: AsRef<str>> Borrow<AsciiCaseInsensitive<str>> for AsciiCaseInsensitive<T> {
+    fn borrow(&self) -> &AsciiCaseInsensitive<str> {
+        AsciiCaseInsensitive::new(self.0.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CFixedString;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_map_lookup() {
+        let mut map = HashMap::new();
+
+        map.insert(AsciiCaseInsensitive(CFixedString::from_str("Accept")), 1);
+        map.insert(AsciiCaseInsensitive(CFixedString::from_str("HOST")), 2);
+
+        assert_eq!(map.get(AsciiCaseInsensitive::new("accept")), Some(&1));
+        assert_eq!(map.get(AsciiCaseInsensitive::new("Host")), Some(&2));
+        assert_eq!(map.get(AsciiCaseInsensitive::new("Hosts")), None);
+
+        assert!(AsciiCaseInsensitive("ÄbC") == AsciiCaseInsensitive("Äbc"));
+        assert!(AsciiCaseInsensitive("ÄbC") != AsciiCaseInsensitive("äbc"));
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{CFixedString, CFixedStringN};
+use core::ffi::{c_char, CStr};
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Returns true if the C string at `ptr` has the same bytes as
+    /// `expected`, for checking what a mocked C function was passed.
+    ///
+    /// A null `ptr` never matches.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null or point to a valid zero terminated string, like
+    /// the pointer from `as_ptr` while its string is still alive.
+    pub unsafe fn ptr_eq_str(ptr: *const c_char, expected: &str) -> bool {
+        !ptr.is_null() && CStr::from_ptr(ptr).to_bytes() == expected.as_bytes()
+    }
+}
+
+/// Asserts that the C string at a pointer equals a `&str`, for tests of
+/// code that passes strings into mocked C functions.
+///
+/// Like `assert_eq!`, a mismatch panics with both values. The pointer is
+/// read like `ptr_eq_str` does, so the macro has to be used inside an
+/// `unsafe` block.
+///
+/// # Safety
+///
+/// The pointer must be null or point to a valid zero terminated string.
+///
+/// ```
+/// use cfixed_string::{assert_c_eq, CFixedString};
+///
+/// let name = CFixedString::from_str("texture.png");
+/// unsafe { assert_c_eq!(name.as_ptr(), "texture.png") };
+/// ```
+///
+/// ```compile_fail
+/// use cfixed_string::{assert_c_eq, CFixedString};
+///
+/// let name = CFixedString::from_str("texture.png");
+/// assert_c_eq!(name.as_ptr(), "texture.png");
+/// ```
+#[macro_export]
+macro_rules! assert_c_eq {
+    ($ptr:expr, $expected:expr $(,)?) => {
+        // A `match` keeps temporaries, such as the string behind the
+        // pointer, alive until the check is done
+        match ($ptr, $expected) {
+            (ptr, expected) => {
+                let (ptr, expected): (*const ::core::ffi::c_char, &str) = (ptr, expected);
+                $crate::__assert_c_eq(ptr, expected)
+            }
+        }
+    };
+}
+
+/// # Safety
+///
+/// Same as for `assert_c_eq!`.
+#[doc(hidden)]
+#[track_caller]
+pub unsafe fn __assert_c_eq(ptr: *const c_char, expected: &str) {
+    if !CFixedString::ptr_eq_str(ptr, expected) {
+        let actual = if ptr.is_null() {
+            "null".into()
+        } else {
+            format!("{:?}", CStr::from_ptr(ptr).to_string_lossy())
+        };
+
+        panic!(
+            "assertion `c_str == expected` failed\n   c_str: {}\nexpected: {:?}",
+            actual, expected
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CFixedString, STRING_SIZE};
+    use core::ffi::c_char;
+    use std::ptr;
+
+    #[test]
+    fn test_assert_c_eq() {
+        let long = "m".repeat(STRING_SIZE * 2);
+        let t = CFixedString::from_str(&long);
+        unsafe {
+            assert_c_eq!(t.as_ptr(), &long);
+            assert_c_eq!(CFixedString::from_str("mocked").as_ptr(), "mocked");
+
+            assert!(!CFixedString::ptr_eq_str(t.as_ptr(), "m"));
+            assert!(!CFixedString::ptr_eq_str(ptr::null(), ""));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "c_str: \"actual\"\nexpected: \"wanted\"")]
+    fn test_assert_c_eq_mismatch() {
+        let t = CFixedString::from_str("actual");
+        unsafe { assert_c_eq!(t.as_ptr() as *const c_char, "wanted") };
+    }
+}
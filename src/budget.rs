@@ -0,0 +1,121 @@
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+/// What to do when a heap fallback would go over the budget set with
+/// `set_heap_budget`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BudgetPolicy {
+    /// Allow the allocation anyway, the usage is still tracked
+    Allow,
+    /// Fail the write and leave the string as it was
+    Error,
+    /// Cut the write to what fits without using more heap memory, which
+    /// means filling up the inline buffer at most
+    Truncate,
+}
+
+pub(crate) struct Budget {
+    limit: AtomicUsize,
+    used: AtomicUsize,
+    policy: AtomicU8,
+}
+
+impl Budget {
+    const fn new() -> Self {
+        Budget {
+            limit: AtomicUsize::new(usize::MAX),
+            used: AtomicUsize::new(0),
+            policy: AtomicU8::new(BudgetPolicy::Allow as u8),
+        }
+    }
+
+    fn set(&self, limit: Option<usize>, policy: BudgetPolicy) {
+        self.limit
+            .store(limit.unwrap_or(usize::MAX), Ordering::Relaxed);
+        self.policy.store(policy as u8, Ordering::Relaxed);
+    }
+
+    pub(crate) fn policy(&self) -> BudgetPolicy {
+        match self.policy.load(Ordering::Relaxed) {
+            0 => BudgetPolicy::Allow,
+            1 => BudgetPolicy::Error,
+            _ => BudgetPolicy::Truncate,
+        }
+    }
+
+    fn used(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    /// Accounts for a heap buffer growing from `old` to `new` bytes.
+    ///
+    /// Returns false without changing anything if that would go over the
+    /// limit, unless the policy allows it.
+    pub(crate) fn try_grow(&self, old: usize, new: usize) -> bool {
+        let limit = match self.policy() {
+            BudgetPolicy::Allow => usize::MAX,
+            _ => self.limit.load(Ordering::Relaxed),
+        };
+
+        self.used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                let next = used.saturating_sub(old).saturating_add(new);
+                if next <= limit {
+                    Some(next)
+                } else {
+                    None
+                }
+            })
+            .is_ok()
+    }
+
+    pub(crate) fn release(&self, bytes: usize) {
+        let _ = self
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                Some(used.saturating_sub(bytes))
+            });
+    }
+}
+
+pub(crate) static HEAP_BUDGET: Budget = Budget::new();
+
+/// Sets a process wide budget for the bytes held by heap allocated strings,
+/// and what to do when a write would go over it.
+///
+/// This is meant for long-running programs where strings coming from
+/// untrusted C input must not grow memory usage without bounds. A `limit`
+/// of `None` removes the budget. Infallible APIs, such as `From<&str>`,
+/// panic when the `Error` policy rejects a write.
+pub fn set_heap_budget(limit: Option<usize>, policy: BudgetPolicy) {
+    HEAP_BUDGET.set(limit, policy);
+}
+
+/// Returns the number of bytes currently held by heap allocated strings
+pub fn heap_usage() -> usize {
+    HEAP_BUDGET.used()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits() {
+        let budget = Budget::new();
+
+        assert!(budget.try_grow(0, 1000));
+        budget.set(Some(1500), BudgetPolicy::Error);
+
+        assert!(!budget.try_grow(0, 600));
+        // growing an existing buffer only counts the difference
+        assert!(budget.try_grow(1000, 1400));
+        assert_eq!(budget.used(), 1400);
+
+        budget.set(Some(1500), BudgetPolicy::Allow);
+        assert!(budget.try_grow(0, 600));
+
+        budget.release(600);
+        budget.release(1400);
+        assert_eq!(budget.used(), 0);
+    }
+}
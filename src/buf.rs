@@ -0,0 +1,125 @@
+use crate::fixed_buf::{fixed_buf_api, FixedBuf};
+use crate::Error;
+use core::ffi::c_char;
+use std::slice;
+
+/// A C string builder on top of a caller-provided buffer.
+///
+/// This is intended for exported `extern "C"` functions that get an output
+/// buffer from their caller. The buffer is always kept zero terminated and
+/// writes that don't fit are cut at the last character that does, so the
+/// caller never sees a partial UTF-8 sequence or a missing terminator.
+pub struct CStrBuf<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> CStrBuf<'a> {
+    /// Wraps `buf` and clears it to an empty string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` is empty as there is no room for the terminator.
+    pub fn new(buf: &'a mut [c_char]) -> Self {
+        assert!(!buf.is_empty(), "CStrBuf needs room for the terminator");
+
+        // `c_char` and `u8` have the same layout
+        let buf = unsafe { slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut u8, buf.len()) };
+
+        buf[0] = 0;
+        CStrBuf { buf, len: 0 }
+    }
+
+    /// Wraps a raw output buffer as handed over by C code.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be non-null and valid for writes of `len` bytes for the
+    /// whole lifetime `'a`, and nothing else may access the buffer meanwhile.
+    pub unsafe fn from_raw_parts(ptr: *mut c_char, len: usize) -> Self {
+        Self::new(slice::from_raw_parts_mut(ptr, len))
+    }
+
+    /// Appends `s`, cutting it at the last character that fits.
+    ///
+    /// Returns an error if `s` had to be cut, either because the buffer is
+    /// full or because `NulPolicy` rejects a zero byte in it.
+    pub fn push_str(&mut self, s: &str) -> Result<(), Error> {
+        self.push(s, true)
+    }
+
+    /// Returns the maximum length of the contents, excluding the terminator
+    pub fn capacity(&self) -> usize {
+        self.buf.len() - 1
+    }
+}
+
+impl<'a> FixedBuf for CStrBuf<'a> {
+    fn buf(&self) -> &[u8] {
+        self.buf
+    }
+
+    fn buf_mut(&mut self) -> &mut [u8] {
+        self.buf
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn set_len(&mut self, len: usize) {
+        self.len = len;
+    }
+}
+
+fixed_buf_api!(impl['a] CStrBuf<'a>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write;
+
+    #[test]
+    fn test_push_and_clear() {
+        let mut storage = [1 as c_char; 16];
+        let mut buf = CStrBuf::new(&mut storage);
+
+        assert!(buf.is_empty());
+        buf.push_str("abc").unwrap();
+        write!(&mut buf, "_{}", 12).unwrap();
+
+        assert_eq!(buf.as_str(), "abc_12");
+        assert_eq!(buf.as_cstr().to_bytes_with_nul(), b"abc_12\0");
+
+        buf.clear();
+        assert_eq!(buf.as_cstr().to_bytes_with_nul(), b"\0");
+    }
+
+    #[test]
+    fn test_truncate_on_full() {
+        let mut storage = [1 as c_char; 6];
+        let mut buf = CStrBuf::new(&mut storage);
+
+        // 'ä' is two bytes and would straddle the end of the buffer
+        assert_eq!(
+            buf.push_str("abcdä"),
+            Err(Error::CapacityExceeded { needed: 7 })
+        );
+        assert_eq!(buf.as_str(), "abcd");
+        assert!(buf.push_str("e").is_ok());
+        assert!(buf.push_str("f").is_err());
+        assert_eq!(buf.as_cstr().to_bytes_with_nul(), b"abcde\0");
+    }
+
+    #[test]
+    fn test_interior_nul() {
+        let mut storage = [1 as c_char; 16];
+        let mut buf = unsafe { CStrBuf::from_raw_parts(storage.as_mut_ptr(), storage.len()) };
+
+        assert_eq!(
+            buf.push_str("ab\0cd"),
+            Err(Error::InteriorNul { position: 2 })
+        );
+        assert_eq!(buf.as_str(), "ab");
+    }
+}
@@ -0,0 +1,208 @@
+use crate::CFixedStringN;
+
+// Written right after the terminator in debug builds. 0xfd is what the MSVC
+// debug heap uses for its guard bytes, so it is easy to spot in a debugger.
+#[cfg(debug_assertions)]
+const CANARY: [u8; 4] = [0xfd; 4];
+
+// Fills the rest of the unused inline buffer in debug builds, so C code
+// reading past the terminator sees an obvious pattern instead of stale data.
+#[cfg(debug_assertions)]
+const POISON: u8 = 0xaa;
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Checks that nothing wrote past the terminator, such as a C function
+    /// that was handed `as_ptr` and overran the string.
+    ///
+    /// Debug builds keep a few guard bytes after the terminator, as far as
+    /// the buffer has room for them, and this panics if they changed other
+    /// than through `fill_remaining`. It is also called on drop. Release
+    /// builds don't have the guard bytes and this does nothing.
+    pub fn verify(&self) {
+        #[cfg(debug_assertions)]
+        {
+            let (buf, end) = self.canary_area();
+
+            // `fill_remaining` replaces the guard bytes along with the rest
+            // of the unused buffer, up to its last byte
+            let expected = match *self {
+                CFixedStringN::Local { ref s, .. } => {
+                    let last = unsafe { s[N - 1].assume_init() } as u8;
+
+                    if buf.first() == Some(&last) {
+                        last
+                    } else {
+                        CANARY[0]
+                    }
+                }
+                _ => CANARY[0],
+            };
+
+            if let Some(offset) = buf.iter().position(|&b| b != expected) {
+                panic!(
+                    "the guard bytes after the terminator were overwritten at offset {}",
+                    end + offset
+                );
+            }
+        }
+    }
+
+    /// Sets every byte of the inline buffer after the terminator to
+    /// `byte`, for when the whole buffer is copied into a C struct that
+    /// expects zero padded storage.
+    ///
+    /// Call it after the last change, as later changes can write over the
+    /// padding again: debug builds keep the guard bytes checked by `verify`
+    /// there. Heap and static strings have no inline buffer and are left
+    /// as they are.
+    pub fn fill_remaining(&mut self, byte: u8) {
+        if let CFixedStringN::Local { ref mut s, len } = *self {
+            for b in &mut s[len + 1..] {
+                *b = std::mem::MaybeUninit::new(byte as _);
+            }
+        }
+    }
+
+    /// Puts the guard bytes back after the terminator, after every change
+    /// to the string.
+    pub(crate) fn write_canary(&mut self) {
+        #[cfg(debug_assertions)]
+        match *self {
+            CFixedStringN::Local { ref mut s, len } => {
+                let end = (len + 1 + CANARY.len()).min(N);
+
+                for b in &mut s[len + 1..end] {
+                    *b = std::mem::MaybeUninit::new(CANARY[0] as _);
+                }
+            }
+            CFixedStringN::Heap { ref mut s, .. } => {
+                for b in s.spare_capacity_mut().iter_mut().take(CANARY.len()) {
+                    *b = std::mem::MaybeUninit::new(CANARY[0]);
+                }
+            }
+            CFixedStringN::Static { .. } => (),
+        }
+    }
+
+    /// Poisons the unused part of the inline buffer and writes the guard
+    /// bytes, after changes that can leave stale bytes past the terminator.
+    pub(crate) fn poison_unused(&mut self) {
+        #[cfg(debug_assertions)]
+        if let CFixedStringN::Local { ref mut s, len } = *self {
+            for b in &mut s[len + 1..] {
+                *b = std::mem::MaybeUninit::new(POISON as _);
+            }
+        }
+
+        self.write_canary();
+    }
+
+    // Returns the guard bytes and the offset they start at
+    #[cfg(debug_assertions)]
+    fn canary_area(&self) -> (&[u8], usize) {
+        use std::slice;
+
+        match *self {
+            CFixedStringN::Local { ref s, len } => {
+                let end = (len + 1 + CANARY.len()).min(N);
+                let buf = unsafe { slice::from_raw_parts(s.as_ptr() as *const u8, end) };
+                (&buf[len + 1..], len + 1)
+            }
+            CFixedStringN::Heap { ref s, .. } => {
+                let n = (s.capacity() - s.len()).min(CANARY.len());
+
+                // Written by `write_canary` through `spare_capacity_mut`
+                let buf = unsafe { slice::from_raw_parts(s.as_ptr().add(s.len()), n) };
+                (buf, s.len())
+            }
+            CFixedStringN::Static { .. } => (&[], 0),
+        }
+    }
+}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use super::{CANARY, POISON};
+    use crate::{CFixedString, CFixedStringN, STRING_SIZE};
+    use std::fmt::Write;
+
+    #[test]
+    fn test_canary_kept() {
+        let mut t = CFixedStringN::<8>::new();
+        t.verify();
+
+        for s in ["abc", "defg", &"h".repeat(STRING_SIZE)] {
+            t.write_str(s).unwrap();
+            t.verify();
+        }
+
+        t.truncate_to_char_boundary(2);
+        t.verify();
+
+        let mut t = CFixedString::from_str("x").into_heap();
+        t.reserve(10).unwrap();
+        t.verify();
+    }
+
+    #[test]
+    #[should_panic(expected = "overwritten at offset 6")]
+    fn test_canary_overrun() {
+        let mut t = CFixedString::from_str("short");
+
+        // A sloppy C function writing one byte too many
+        if let CFixedString::Local { ref mut s, .. } = t {
+            s[6] = std::mem::MaybeUninit::new(b'!' as _);
+        }
+
+        drop(t);
+    }
+
+    #[test]
+    fn test_unused_poisoned() {
+        let mut t = CFixedStringN::<16>::from_str("long enough");
+        t.truncate_to_char_boundary(2);
+
+        if let CFixedStringN::Local { ref s, .. } = t {
+            let bytes: Vec<u8> = s.iter().map(|b| unsafe { b.assume_init() } as u8).collect();
+
+            assert_eq!(&bytes[..3], b"lo\0");
+            assert_eq!(&bytes[3..7], &CANARY);
+            assert!(bytes[7..].iter().all(|&b| b == POISON));
+        }
+    }
+
+    #[test]
+    fn test_fill_remaining() {
+        let mut t = CFixedStringN::<16>::from_str("name");
+        t.fill_remaining(0);
+        t.verify();
+
+        let mut record = [0xffu8; 16];
+        if let CFixedStringN::Local { ref s, .. } = t {
+            for (dst, src) in record.iter_mut().zip(s) {
+                *dst = unsafe { src.assume_init() } as u8;
+            }
+        }
+        assert_eq!(&record, b"name\0\0\0\0\0\0\0\0\0\0\0\0");
+
+        t.push_str("s");
+        t.fill_remaining(b' ');
+        t.verify();
+        assert_eq!(t, "names");
+
+        let mut t = CFixedStringN::<4>::from_str("heap");
+        t.fill_remaining(0);
+        assert_eq!(t, "heap");
+    }
+
+    #[test]
+    #[should_panic(expected = "overwritten at offset 7")]
+    fn test_overrun_after_fill() {
+        let mut t = CFixedStringN::<16>::from_str("short");
+        t.fill_remaining(0);
+
+        if let CFixedStringN::Local { ref mut s, .. } = t {
+            s[7] = std::mem::MaybeUninit::new(b'!' as _);
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::CFixedString;
+use core::ffi::{c_char, CStr};
+use std::ffi::CString;
+use std::ops;
+
+/// A C string that is either borrowed from the caller or built from a
+/// plain `&str`.
+///
+/// Wrapper functions can take `impl Into<CFixedCow<'a>>` so callers that
+/// already have a `&CStr` or `CString` pass it through without a copy, while
+/// `&str` input is converted into a `CFixedString` as usual.
+///
+/// ```
+/// use cfixed_string::CFixedCow;
+/// use std::ffi::CStr;
+///
+/// fn set_title<'a>(title: impl Into<CFixedCow<'a>>) -> usize {
+///     let title = title.into();
+///     title.to_bytes().len()
+/// }
+///
+/// assert_eq!(set_title("from str"), 8);
+/// assert_eq!(set_title(CStr::from_bytes_with_nul(b"from C\0").unwrap()), 6);
+/// ```
+#[allow(clippy::large_enum_variant)]
+pub enum CFixedCow<'a> {
+    /// A C string owned by the caller
+    Borrowed(&'a CStr),
+    /// A copy made from a `&str`
+    Owned(CFixedString),
+}
+
+impl<'a> CFixedCow<'a> {
+    /// Returns the pointer to be passed down to the C code
+    pub fn as_ptr(&self) -> *const c_char {
+        match *self {
+            CFixedCow::Borrowed(s) => s.as_ptr(),
+            CFixedCow::Owned(ref s) => s.as_ptr(),
+        }
+    }
+
+    /// Returns true if the string is borrowed without a copy
+    pub fn is_borrowed(&self) -> bool {
+        matches!(*self, CFixedCow::Borrowed(_))
+    }
+
+    /// Returns the string as a `CStr`
+    pub fn as_cstr(&self) -> &CStr {
+        match *self {
+            CFixedCow::Borrowed(s) => s,
+            CFixedCow::Owned(ref s) => s,
+        }
+    }
+}
+
+impl<'a> ops::Deref for CFixedCow<'a> {
+    type Target = CStr;
+
+    fn deref(&self) -> &CStr {
+        self.as_cstr()
+    }
+}
+
+impl<'a> AsRef<CStr> for CFixedCow<'a> {
+    fn as_ref(&self) -> &CStr {
+        self
+    }
+}
+
+impl<'a> From<&'a CStr> for CFixedCow<'a> {
+    fn from(s: &'a CStr) -> Self {
+        CFixedCow::Borrowed(s)
+    }
+}
+
+impl<'a> From<&'a CString> for CFixedCow<'a> {
+    fn from(s: &'a CString) -> Self {
+        CFixedCow::Borrowed(s)
+    }
+}
+
+impl<'a> From<&'a CFixedString> for CFixedCow<'a> {
+    fn from(s: &'a CFixedString) -> Self {
+        CFixedCow::Borrowed(s)
+    }
+}
+
+impl<'a> From<&'a str> for CFixedCow<'a> {
+    fn from(s: &'a str) -> Self {
+        CFixedCow::Owned(CFixedString::from(s))
+    }
+}
+
+impl<'a> From<&'a String> for CFixedCow<'a> {
+    fn from(s: &'a String) -> Self {
+        CFixedCow::from(s.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn len<'a>(s: impl Into<CFixedCow<'a>>) -> (usize, bool) {
+        let s = s.into();
+        (s.to_bytes().len(), s.is_borrowed())
+    }
+
+    #[test]
+    fn test_cow() {
+        let owned = CString::new("owned").unwrap();
+        let fixed = CFixedString::from_str("fixed");
+
+        assert_eq!(len("text"), (4, false));
+        assert_eq!(len(&"string".to_owned()), (6, false));
+        assert_eq!(len(owned.as_c_str()), (5, true));
+        assert_eq!(len(&owned), (5, true));
+        assert_eq!(len(&fixed), (5, true));
+
+        let cow = CFixedCow::from(&owned);
+        assert_eq!(cow.as_ptr(), owned.as_ptr());
+    }
+}
@@ -0,0 +1,640 @@
+use crate::{floor_char_boundary, CFixedStringN, Error};
+use std::ops::{Bound, RangeBounds};
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Empties the string, keeping a heap buffer for the next writes.
+    ///
+    /// A string reused for every frame or log line then only allocates the
+    /// first time it grows past the inline buffer.
+    pub fn clear(&mut self) {
+        self.truncate_bytes(0);
+    }
+
+    /// Shortens the string to `new_len` bytes, excluding the terminator,
+    /// and does nothing if it is already shorter.
+    ///
+    /// Panics like `String::truncate` if `new_len` is inside a character,
+    /// use `truncate_to_char_boundary` to round down instead.
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len < self.to_bytes().len() {
+            self.assert_char_boundary(new_len);
+            self.truncate_bytes(new_len);
+        }
+    }
+
+    /// Appends `c`, moving the string to the heap if it doesn't fit.
+    ///
+    /// Panics like `From<&str>` if `NulPolicy::Reject` rejects a `'\0'` or
+    /// the heap budget is exhausted, see `try_push`.
+    pub fn push(&mut self, c: char) {
+        self.try_push(c).unwrap();
+    }
+
+    /// Appends `s`, moving the string to the heap if it doesn't fit.
+    ///
+    /// Panics in the same cases as `push`, see `try_push_str`.
+    pub fn push_str(&mut self, s: &str) {
+        self.try_push_str(s).unwrap();
+    }
+
+    /// Appends `c`, failing instead of panicking like `try_push_str`
+    pub fn try_push(&mut self, c: char) -> Result<(), Error> {
+        self.push_bytes(c.encode_utf8(&mut [0; 4]).as_bytes())
+    }
+
+    /// Creates a string of `pattern` repeated `n` times, such as a run of
+    /// separators or padding, inline if it fits.
+    ///
+    /// Panics in the same cases as `push`.
+    pub fn repeat(pattern: &str, n: usize) -> Self {
+        let mut t = Self::new();
+        t.push_repeat(pattern, n);
+        t
+    }
+
+    /// Appends `pattern` repeated `n` times, growing the buffer at most
+    /// once.
+    ///
+    /// Panics in the same cases as `push`.
+    pub fn push_repeat(&mut self, pattern: &str, n: usize) {
+        let total = pattern.len().checked_mul(n).expect("capacity overflow");
+
+        // Only saves reallocations, the pushes apply the budget policy
+        let _ = self.reserve(total);
+
+        for _ in 0..n {
+            self.push_str(pattern);
+        }
+    }
+
+    /// Appends `byte` until the string is `width` bytes long, excluding the
+    /// terminator, for the fixed width fields of legacy C record formats.
+    ///
+    /// Longer strings are left as they are. `byte` must be ASCII so the
+    /// string stays valid UTF-8, and this panics otherwise. A zero `byte`
+    /// fails with `InteriorNul` whatever the `NulPolicy` is, as replacing
+    /// or dropping it would miss `width`.
+    pub fn pad_to(&mut self, width: usize, byte: u8) -> Result<(), Error> {
+        assert!(byte.is_ascii(), "padding byte {:#x} is not ASCII", byte);
+
+        if byte == 0 {
+            return Err(Error::InteriorNul { position: 0 });
+        }
+
+        let missing = width.saturating_sub(self.to_bytes().len());
+
+        // Only saves reallocations, the pushes apply the budget policy
+        let _ = self.reserve(missing);
+
+        let chunk = [byte; 32];
+
+        for n in (0..missing).step_by(chunk.len()) {
+            self.push_bytes(&chunk[..(missing - n).min(chunk.len())])?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the last character and returns it, or `None` if the string
+    /// is empty.
+    ///
+    /// Invalid UTF-8 at the end, which only C code can leave behind, is
+    /// removed one byte at a time, each returned as `U+FFFD REPLACEMENT
+    /// CHARACTER`.
+    pub fn pop(&mut self) -> Option<char> {
+        let bytes = self.to_bytes();
+        let (c, len) = last_char(bytes)?;
+
+        self.truncate_bytes(bytes.len() - len);
+        Some(c)
+    }
+
+    /// Inserts `c` at byte offset `idx`.
+    ///
+    /// Panics like `String::insert` if `idx` is past the end or inside a
+    /// character, and in the same cases as `push`.
+    pub fn insert(&mut self, idx: usize, c: char) {
+        self.insert_str(idx, c.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Inserts `s` at byte offset `idx`, panicking in the same cases as
+    /// `insert`
+    pub fn insert_str(&mut self, idx: usize, s: &str) {
+        self.assert_char_boundary(idx);
+        self.splice_bytes(idx, idx, s.as_bytes()).unwrap();
+    }
+
+    /// Removes the character at byte offset `idx` and returns it.
+    ///
+    /// Panics like `String::remove` if `idx` is not the start of a
+    /// character in the string.
+    pub fn remove(&mut self, idx: usize) -> char {
+        self.assert_char_boundary(idx);
+
+        let c = unsafe { self.as_str() }[idx..]
+            .chars()
+            .next()
+            .expect("cannot remove a char from the end of a string");
+
+        self.splice_bytes(idx, idx + c.len_utf8(), &[]).unwrap();
+        c
+    }
+
+    /// Replaces the byte range `range` with `s`, moving the rest of the
+    /// string and its terminator to fit, such as when filling in a `%s`
+    /// placeholder of a template.
+    ///
+    /// Panics like `String::replace_range` if either end of the range is
+    /// out of bounds or inside a character, and in the same cases as
+    /// `push`.
+    pub fn replace_range<R: RangeBounds<usize>>(&mut self, range: R, s: &str) {
+        let start = match range.start_bound() {
+            Bound::Included(&i) => i,
+            Bound::Excluded(&i) => i
+                .checked_add(1)
+                .expect("attempted to index slice from after maximum usize"),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&i) => i
+                .checked_add(1)
+                .expect("attempted to index slice up to maximum usize"),
+            Bound::Excluded(&i) => i,
+            Bound::Unbounded => self.to_bytes().len(),
+        };
+
+        assert!(
+            start <= end,
+            "range start {} is past its end {}",
+            start,
+            end
+        );
+        self.assert_char_boundary(start);
+        self.assert_char_boundary(end);
+
+        self.splice_bytes(start, end, s.as_bytes()).unwrap();
+    }
+
+    /// Keeps only the characters for which `f` returns true, such as to
+    /// strip control characters before a string goes to a C UI library.
+    ///
+    /// Works in place without allocating. Invalid UTF-8, which only C code
+    /// can leave behind, is passed to `f` as `U+FFFD REPLACEMENT CHARACTER`
+    /// one byte at a time.
+    pub fn retain<F: FnMut(char) -> bool>(&mut self, mut f: F) {
+        self.make_owned();
+
+        let content = self.content_mut();
+        let (mut read, mut write) = (0, 0);
+
+        while read < content.len() {
+            let (c, len) = next_char(&content[read..]);
+
+            if f(c) {
+                content.copy_within(read..read + len, write);
+                write += len;
+            }
+
+            read += len;
+        }
+
+        self.truncate_bytes(write);
+    }
+
+    /// Removes leading and trailing whitespace, as `str::trim` defines it,
+    /// moving the rest of the string to the start of the buffer.
+    ///
+    /// Nothing is allocated, a heap string keeps its buffer.
+    pub fn trim_in_place(&mut self) {
+        self.trim_end_in_place();
+        self.trim_start_in_place();
+    }
+
+    /// Removes leading whitespace, see `trim_in_place`
+    pub fn trim_start_in_place(&mut self) {
+        let bytes = self.to_bytes();
+        let mut start = 0;
+
+        while start < bytes.len() {
+            match next_char(&bytes[start..]) {
+                (c, len) if c.is_whitespace() => start += len,
+                _ => break,
+            }
+        }
+
+        if start > 0 {
+            self.splice_bytes(0, start, &[]).unwrap();
+        }
+    }
+
+    /// Removes trailing whitespace, see `trim_in_place`
+    pub fn trim_end_in_place(&mut self) {
+        let bytes = self.to_bytes();
+        let mut end = bytes.len();
+
+        while let Some((c, len)) = last_char(&bytes[..end]) {
+            if !c.is_whitespace() {
+                break;
+            }
+
+            end -= len;
+        }
+
+        if end < bytes.len() {
+            self.truncate_bytes(end);
+        }
+    }
+
+    /// Converts ASCII letters to upper case in place, for C APIs that
+    /// expect canonical identifiers. Other characters are left as they
+    /// are.
+    pub fn make_ascii_uppercase(&mut self) {
+        self.make_owned();
+        self.content_mut().make_ascii_uppercase();
+    }
+
+    /// Converts ASCII letters to lower case in place, see
+    /// `make_ascii_uppercase`
+    pub fn make_ascii_lowercase(&mut self) {
+        self.make_owned();
+        self.content_mut().make_ascii_lowercase();
+    }
+
+    /// Replaces the bytes in `start..end` with `bytes`, which is how all
+    /// edits in the middle of the string are done.
+    ///
+    /// `bytes` is appended first, growing the string like any other write,
+    /// and then rotated into place, so both ends must be char boundaries
+    /// for the result to stay valid UTF-8.
+    pub(crate) fn splice_bytes(
+        &mut self,
+        start: usize,
+        end: usize,
+        bytes: &[u8],
+    ) -> Result<(), Error> {
+        let old_len = self.to_bytes().len();
+        debug_assert!(start <= end && end <= old_len);
+
+        // Also turns a static string into an owned one
+        self.push_bytes(bytes)?;
+
+        let added = self.to_bytes().len() - old_len;
+        let content = self.content_mut();
+        content[start..].rotate_right(added);
+        content[start + added..].rotate_left(end - start);
+
+        self.truncate_bytes(old_len + added - (end - start));
+        Ok(())
+    }
+
+    // Copies a static string so it can be changed in place
+    pub(crate) fn make_owned(&mut self) {
+        if let CFixedStringN::Static { s } = *self {
+            // Panics like `From<&str>` if the budget rejects the copy
+            *self = Self::copied(s.to_bytes()).unwrap();
+        }
+    }
+
+    // The bytes of an owned string, excluding the terminator
+    fn content_mut(&mut self) -> &mut [u8] {
+        match *self {
+            CFixedStringN::Local { ref mut s, len } => unsafe {
+                std::slice::from_raw_parts_mut(s.as_mut_ptr() as *mut u8, len)
+            },
+            CFixedStringN::Heap { ref mut s, len } => &mut s[..len],
+            CFixedStringN::Static { .. } => unreachable!(),
+        }
+    }
+
+    fn assert_char_boundary(&self, idx: usize) {
+        let bytes = self.to_bytes();
+
+        assert!(
+            idx <= bytes.len() && floor_char_boundary(bytes, idx) == idx,
+            "byte index {} is not a char boundary",
+            idx
+        );
+    }
+}
+
+// Decodes the first character of `bytes`, returning an invalid byte as
+// `U+FFFD`, and how many bytes it took
+fn next_char(bytes: &[u8]) -> (char, usize) {
+    let head = &bytes[..bytes.len().min(4)];
+
+    let valid = match std::str::from_utf8(head) {
+        Ok(s) => s,
+        Err(e) => unsafe { std::str::from_utf8_unchecked(&head[..e.valid_up_to()]) },
+    };
+
+    match valid.chars().next() {
+        Some(c) => (c, c.len_utf8()),
+        None => (char::REPLACEMENT_CHARACTER, 1),
+    }
+}
+
+// Decodes the last character of `bytes` like `next_char`, the last byte is
+// returned as `U+FFFD` unless it ends a valid sequence
+fn last_char(bytes: &[u8]) -> Option<(char, usize)> {
+    let end = bytes.len();
+    let start = floor_char_boundary(bytes, end.checked_sub(1)?);
+
+    let mut chars = match std::str::from_utf8(&bytes[start..]) {
+        Ok(s) => s.chars(),
+        Err(_) => "".chars(),
+    };
+
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some((c, end - start)),
+        _ => Some((char::REPLACEMENT_CHARACTER, 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::last_char;
+    use crate::{floor_char_boundary, CFixedString, CFixedStringN, Error, STRING_SIZE};
+    use std::ops::Bound;
+
+    #[test]
+    fn test_clear() {
+        let mut t = CFixedString::from_str("short");
+        t.clear();
+        assert_eq!(t, "");
+        assert!(!t.is_allocated());
+
+        let mut t = CFixedString::from_str("l".repeat(STRING_SIZE * 2));
+        let ptr = t.as_ptr();
+        let capacity = t.capacity();
+
+        t.clear();
+        assert_eq!(t.to_bytes_with_nul(), b"\0");
+        assert_eq!((t.as_ptr(), t.capacity()), (ptr, capacity));
+
+        t += "reused";
+        assert_eq!(t.as_ptr(), ptr);
+        assert_eq!(t, "reused");
+
+        let mut t = CFixedString::from_static("static\0");
+        t.clear();
+        assert!(!t.is_static());
+        assert_eq!(t, "");
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut t = CFixedString::from_str("gr\u{f6}\u{df}e");
+        t.truncate(10);
+        assert_eq!(t, "gr\u{f6}\u{df}e");
+
+        t.truncate(4);
+        assert_eq!(t.to_bytes_with_nul(), "gr\u{f6}\0".as_bytes());
+
+        let mut t = CFixedString::from_str("t".repeat(STRING_SIZE * 2));
+        t.truncate(STRING_SIZE);
+        assert!(t.is_allocated());
+        assert_eq!(t.to_bytes().len(), STRING_SIZE);
+    }
+
+    #[test]
+    #[should_panic(expected = "not a char boundary")]
+    fn test_truncate_inside_char() {
+        CFixedString::from_str("gr\u{f6}").truncate(3);
+    }
+
+    #[test]
+    fn test_push() {
+        let mut t = CFixedString::new();
+        t.push('/');
+        t.push_str("usr");
+        t.push('\u{e5}');
+        assert_eq!(t, "/usr\u{e5}");
+
+        t.push_str(&"p".repeat(STRING_SIZE));
+        assert!(t.is_allocated());
+
+        assert_eq!(t.try_push('\0'), Err(Error::InteriorNul { position: 0 }));
+        assert_eq!(t.to_bytes().len(), 6 + STRING_SIZE);
+    }
+
+    #[test]
+    fn test_pop() {
+        let mut t = CFixedString::from_str("a/\u{e5}\u{1f600}/");
+        assert_eq!(t.pop(), Some('/'));
+        assert_eq!(t.pop(), Some('\u{1f600}'));
+        assert_eq!(t.pop(), Some('\u{e5}'));
+        assert_eq!(t.to_bytes_with_nul(), b"a/\0");
+
+        let mut t = CFixedString::from_str(format!("{}/", "p".repeat(STRING_SIZE)));
+        assert_eq!(t.pop(), Some('/'));
+        assert!(t.is_allocated());
+
+        let mut t = CFixedString::new();
+        assert_eq!(t.pop(), None);
+
+        // Stray bytes C code left behind don't take valid characters along
+        let fffd = char::REPLACEMENT_CHARACTER;
+        assert_eq!(last_char(b"a\x80\x80"), Some((fffd, 1)));
+        assert_eq!(floor_char_boundary(b"a\x80\x80", 2), 2);
+        assert_eq!(last_char(b"a\x80"), Some((fffd, 1)));
+        assert_eq!(last_char(b"\xc3\xa9\x80"), Some((fffd, 1)));
+        assert_eq!(floor_char_boundary(b"\xc3\xa9\x80", 2), 2);
+        assert_eq!(last_char(b"\xc3\xa9"), Some(('\u{e9}', 2)));
+        assert_eq!(last_char(b"\xe2\x82"), Some((fffd, 1)));
+        assert_eq!(last_char(b"\xe2"), Some((fffd, 1)));
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut t = CFixedString::from_str("usr/lib");
+        t.insert(0, '/');
+        t.insert_str(4, "/local");
+        t.insert(t.to_bytes().len(), '\u{e5}');
+        assert_eq!(t, "/usr/local/lib\u{e5}");
+
+        assert_eq!(t.remove(14), '\u{e5}');
+        assert_eq!(t.remove(0), '/');
+        assert_eq!(t.to_bytes_with_nul(), b"usr/local/lib\0");
+
+        let long = "i".repeat(STRING_SIZE);
+        t.insert_str(3, &long);
+        assert!(t.is_allocated());
+        assert_eq!(&t[..3], "usr");
+        assert_eq!(&t[3 + STRING_SIZE..], "/local/lib");
+
+        let mut t = CFixedString::from_static("static\0");
+        assert_eq!(t.remove(5), 'c');
+        assert_eq!(t, "stati");
+    }
+
+    #[test]
+    #[should_panic(expected = "byte index 1 is not a char boundary")]
+    fn test_insert_inside_char() {
+        CFixedString::from_str("\u{e5}\u{e5}").insert(1, 'x');
+    }
+
+    #[test]
+    #[should_panic(expected = "from the end")]
+    fn test_remove_at_end() {
+        CFixedString::from_str("ab").remove(2);
+    }
+
+    #[test]
+    fn test_replace_range() {
+        let mut t = CFixedString::from_str("texture %s not found");
+        t.replace_range(8..10, "'grass.png'");
+        assert_eq!(t, "texture 'grass.png' not found");
+
+        t.replace_range(..=6, "file");
+        t.replace_range(4.., "");
+        assert_eq!(t.to_bytes_with_nul(), b"file\0");
+
+        let long = "r".repeat(STRING_SIZE);
+        t.replace_range(1..3, &long);
+        assert!(t.is_allocated());
+        assert_eq!(t.to_bytes().len(), 2 + STRING_SIZE);
+
+        t.replace_range(.., "\u{e5}");
+        assert_eq!(t, "\u{e5}");
+    }
+
+    #[test]
+    #[should_panic(expected = "byte index 1 is not a char boundary")]
+    fn test_replace_range_inside_char() {
+        CFixedString::from_str("\u{e5}").replace_range(1.., "x");
+    }
+
+    #[test]
+    #[should_panic(expected = "attempted to index slice up to maximum usize")]
+    fn test_replace_range_max_end() {
+        CFixedString::from_str("ab").replace_range(0..=usize::MAX, "x");
+    }
+
+    #[test]
+    #[should_panic(expected = "attempted to index slice from after maximum usize")]
+    fn test_replace_range_max_start() {
+        let range = (Bound::Excluded(usize::MAX), Bound::Unbounded);
+        CFixedString::from_str("ab").replace_range(range, "x");
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut t = CFixedString::from_str("na\u{7}me\t\u{e5}\r\n");
+        t.retain(|c| !c.is_control());
+        assert_eq!(t, "name\u{e5}");
+
+        let mut t = CFixedString::from_str("a-".repeat(STRING_SIZE));
+        t.retain(|c| c != '-');
+        assert!(t.is_allocated());
+        assert_eq!(t, "a".repeat(STRING_SIZE));
+
+        let mut t = CFixedString::from_static("s t a t i c\0");
+        t.retain(|c| c != ' ');
+        assert_eq!(t, "static");
+
+        let mut t = CFixedString::new();
+        if let CFixedString::Local {
+            ref mut s,
+            ref mut len,
+        } = t
+        {
+            for (i, &b) in b"a\xffb\0".iter().enumerate() {
+                s[i] = std::mem::MaybeUninit::new(b as _);
+            }
+            *len = 3;
+        }
+        t.write_canary();
+
+        let mut seen = Vec::new();
+        t.retain(|c| {
+            seen.push(c);
+            c != '\u{fffd}'
+        });
+        assert_eq!(seen, ['a', '\u{fffd}', 'b']);
+        assert_eq!(t, "ab");
+    }
+
+    #[test]
+    fn test_ascii_case() {
+        let mut t = CFixedString::from_str("Gl_Vertex_\u{e5}");
+        t.make_ascii_uppercase();
+        assert_eq!(t, "GL_VERTEX_\u{e5}");
+        t.make_ascii_lowercase();
+        assert_eq!(t, "gl_vertex_\u{e5}");
+
+        let mut t = CFixedString::from_static("Static\0");
+        t.make_ascii_uppercase();
+        assert_eq!(t, "STATIC");
+
+        let mut t = CFixedString::from_str("x".repeat(STRING_SIZE));
+        t.make_ascii_uppercase();
+        assert_eq!(t, "X".repeat(STRING_SIZE));
+    }
+
+    #[test]
+    fn test_trim_in_place() {
+        let mut t = CFixedString::from_str(" \t user name\u{3000}\r\n");
+        t.trim_end_in_place();
+        assert_eq!(t, " \t user name");
+        t.trim_start_in_place();
+        assert_eq!(t.to_bytes_with_nul(), b"user name\0");
+
+        let mut t = CFixedString::from_str(format!("  {}  ", "w".repeat(STRING_SIZE)));
+        let ptr = t.as_ptr();
+        t.trim_in_place();
+        assert_eq!(t, "w".repeat(STRING_SIZE));
+        assert_eq!(t.as_ptr(), ptr);
+
+        let mut t = CFixedString::from_str(" \n ");
+        t.trim_in_place();
+        assert_eq!(t, "");
+
+        let mut t = CFixedString::from_static(" static \0");
+        t.trim_in_place();
+        assert_eq!(t, "static");
+    }
+
+    #[test]
+    fn test_repeat() {
+        let t = CFixedString::repeat("-", 8);
+        assert_eq!(t, "--------");
+        assert!(!t.is_allocated());
+
+        let t = CFixedStringN::<16>::repeat("=\u{e5}", 10);
+        assert!(t.is_allocated());
+        assert_eq!(t.capacity(), 30);
+        assert_eq!(t, "=\u{e5}".repeat(10));
+
+        let mut t = CFixedString::from_str("[");
+        t.push_repeat(" ", 3);
+        t.push_repeat("x", 0);
+        t.push(']');
+        assert_eq!(t, "[   ]");
+    }
+
+    #[test]
+    fn test_pad_to() {
+        let mut t = CFixedString::from_str("NAME");
+        t.pad_to(8, b' ').unwrap();
+        assert_eq!(t.to_bytes_with_nul(), b"NAME    \0");
+
+        t.pad_to(4, b'.').unwrap();
+        assert_eq!(t, "NAME    ");
+
+        t.pad_to(STRING_SIZE + 40, b'.').unwrap();
+        assert!(t.is_allocated());
+        assert_eq!(t.to_bytes().len(), STRING_SIZE + 40);
+        assert!(t.to_bytes()[8..].iter().all(|&b| b == b'.'));
+
+        let mut t = CFixedString::new();
+        assert_eq!(t.pad_to(2, 0), Err(Error::InteriorNul { position: 0 }));
+        assert_eq!(t.pad_to(0, 0), Err(Error::InteriorNul { position: 0 }));
+        assert_eq!(t, "");
+    }
+
+    #[test]
+    #[should_panic(expected = "padding byte 0xe9 is not ASCII")]
+    fn test_pad_to_non_ascii() {
+        let _ = CFixedString::new().pad_to(4, 0xe9);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{CFixedStringN, STRING_SIZE};
+use std::fmt;
+use std::str::Utf8Error;
+
+/// The error type for the fallible operations of this crate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The input contained a zero byte at `position`
+    InteriorNul {
+        /// Byte offset of the zero byte in the input
+        position: usize,
+    },
+    /// The result doesn't fit the available space
+    CapacityExceeded {
+        /// Bytes needed, including the terminator
+        needed: usize,
+    },
+    /// Moving to the heap would go over the budget set with
+    /// `set_heap_budget`
+    HeapBudgetExceeded {
+        /// Bytes the heap allocation would need
+        needed: usize,
+    },
+    /// The allocator passed to `new_in` couldn't provide the memory
+    AllocationFailed {
+        /// Bytes the heap allocation would need
+        needed: usize,
+    },
+    /// The input is not valid UTF-8
+    InvalidUtf8 {
+        /// Byte offset of the first invalid sequence
+        offset: usize,
+    },
+    /// The input is not valid UTF-16
+    InvalidUtf16 {
+        /// Offset of the unpaired surrogate, in code units
+        offset: usize,
+    },
+    /// A `Display` implementation returned an error
+    Format,
+    /// A range is outside of the memory it should be read from or written to
+    OutOfBounds {
+        /// Start of the range
+        offset: usize,
+        /// Length of the range in bytes
+        len: usize,
+    },
+}
+
+/// The name `Error` goes by when it is imported next to other error types.
+pub type CFixedStringError = Error;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Error::InteriorNul { position } => {
+                write!(f, "zero byte found in input at position {}", position)
+            }
+            Error::CapacityExceeded { needed } => {
+                write!(f, "capacity exceeded, {} bytes needed", needed)
+            }
+            Error::HeapBudgetExceeded { needed } => {
+                write!(f, "heap budget exceeded, {} bytes needed", needed)
+            }
+            Error::AllocationFailed { needed } => {
+                write!(f, "allocation failed, {} bytes needed", needed)
+            }
+            Error::InvalidUtf8 { offset } => {
+                write!(f, "invalid UTF-8 sequence at offset {}", offset)
+            }
+            Error::InvalidUtf16 { offset } => {
+                write!(f, "unpaired UTF-16 surrogate at offset {}", offset)
+            }
+            Error::Format => f.write_str("formatting error"),
+            Error::OutOfBounds { offset, len } => {
+                write!(f, "{} bytes at offset {} are out of bounds", len, offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Utf8Error> for Error {
+    fn from(e: Utf8Error) -> Self {
+        Error::InvalidUtf8 {
+            offset: e.valid_up_to(),
+        }
+    }
+}
+
+/// The error returned by `CFixedString::into_string` when the contents are
+/// not valid UTF-8.
+///
+/// The original string can be recovered with `into_cfixed_string`.
+pub struct IntoStringError<const N: usize = STRING_SIZE> {
+    pub(crate) inner: CFixedStringN<N>,
+    pub(crate) error: Utf8Error,
+}
+
+impl<const N: usize> IntoStringError<N> {
+    /// Returns the string that failed to convert
+    pub fn into_cfixed_string(self) -> CFixedStringN<N> {
+        self.inner
+    }
+
+    /// Returns the UTF-8 error describing where the conversion failed
+    pub fn utf8_error(&self) -> Utf8Error {
+        self.error
+    }
+}
+
+impl<const N: usize> fmt::Debug for IntoStringError<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IntoStringError")
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<const N: usize> fmt::Display for IntoStringError<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "C string contained invalid UTF-8: {}", self.error)
+    }
+}
+
+impl<const N: usize> std::error::Error for IntoStringError<N> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl<const N: usize> From<IntoStringError<N>> for Error {
+    fn from(e: IntoStringError<N>) -> Self {
+        e.error.into()
+    }
+}
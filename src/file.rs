@@ -0,0 +1,127 @@
+use crate::{CFixedStringN, Error};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Reads a small file, such as a token, a PID file or a `/proc` entry,
+    /// straight into the inline buffer.
+    ///
+    /// Files that don't fit move to the heap like any other long string.
+    /// Contents with zero bytes or invalid UTF-8 are rejected with
+    /// `InvalidData`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut inline = [0; N];
+        let mut heap = Vec::new();
+        let bytes = read_file(path.as_ref(), &mut inline, &mut heap)?;
+
+        if let Some(position) = bytes.iter().position(|&b| b == 0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                Error::InteriorNul { position },
+            ));
+        }
+
+        std::str::from_utf8(bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, Error::from(e)))?;
+
+        let mut s = Self::new();
+        s.push_bytes(bytes).map_err(io::Error::other)?;
+
+        Ok(s)
+    }
+
+    /// Reads a small file like `from_file`, but stops at the first zero
+    /// byte and replaces invalid UTF-8 with `U+FFFD REPLACEMENT CHARACTER`
+    /// instead of failing.
+    ///
+    /// This suits `/proc` entries and files written by C code that may
+    /// keep a trailing terminator.
+    pub fn from_file_lossy<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut inline = [0; N];
+        let mut heap = Vec::new();
+        let bytes = read_file(path.as_ref(), &mut inline, &mut heap)?;
+
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        let text = String::from_utf8_lossy(&bytes[..end]);
+
+        let mut s = Self::new();
+        s.push_bytes(text.as_bytes()).map_err(io::Error::other)?;
+
+        Ok(s)
+    }
+}
+
+// Fills `inline` first and only falls back to `heap` once the file turns out
+// to be larger, so small files are read without allocating.
+fn read_file<'a, const N: usize>(
+    path: &Path,
+    inline: &'a mut [u8; N],
+    heap: &'a mut Vec<u8>,
+) -> io::Result<&'a [u8]> {
+    let mut file = File::open(path)?;
+    let mut filled = 0;
+
+    while filled < inline.len() {
+        match file.read(&mut inline[filled..]) {
+            Ok(0) => return Ok(&inline[..filled]),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    heap.extend_from_slice(&inline[..]);
+    file.read_to_end(heap)?;
+
+    Ok(&heap[..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CFixedString, STRING_SIZE};
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("cfixed-string-{}-{}", std::process::id(), name));
+
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_from_file() {
+        let short = temp_file("short", b"1234\n");
+        let long = temp_file("long", "x".repeat(STRING_SIZE * 2).as_bytes());
+
+        let t = CFixedString::from_file(&short).unwrap();
+        assert!(!t.is_allocated());
+        assert_eq!(&t.to_string(), "1234\n");
+
+        let t = CFixedString::from_file(&long).unwrap();
+        assert!(t.is_allocated());
+        assert_eq!(t.to_bytes().len(), STRING_SIZE * 2);
+
+        fs::remove_file(short).unwrap();
+        fs::remove_file(long).unwrap();
+    }
+
+    #[test]
+    fn test_from_file_invalid() {
+        let path = temp_file("invalid", b"comm\xff\0rest");
+
+        let err = CFixedString::from_file(&path).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let t = CFixedString::from_file_lossy(&path).unwrap();
+        assert_eq!(&t.to_string(), "comm\u{fffd}");
+
+        let missing = CFixedString::from_file(path.with_extension("missing"));
+        assert_eq!(missing.err().unwrap().kind(), io::ErrorKind::NotFound);
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,269 @@
+use crate::budget::HEAP_BUDGET;
+use crate::{CFixedStringN, Error};
+use core::ffi::c_char;
+use std::mem;
+
+// Enough for C functions whose required size changes between calls, such as
+// an environment variable being updated by another thread.
+const MAX_RETRIES: usize = 4;
+
+/// The outcome of one call to the closure passed to
+/// `CFixedString::fill_with_retry`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FillResult {
+    /// The string fit, this many bytes were written excluding the terminator
+    Written(usize),
+    /// The buffer was too small, this many bytes including the terminator
+    /// are needed
+    TooSmall(usize),
+}
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Fills the string through a C function that reports the size it needs
+    /// when its output buffer is too small.
+    ///
+    /// `f` gets a buffer pointer and its size in bytes, including room for
+    /// the terminator. The inline buffer is tried first and, when `f` asks
+    /// for more, a heap buffer of the requested size is handed over and the
+    /// call is retried a bounded number of times.
+    ///
+    /// The written bytes must not contain zeros and must be valid UTF-8.
+    pub fn fill_with_retry<F>(mut f: F) -> Result<Self, Error>
+    where
+        F: FnMut(*mut c_char, usize) -> FillResult,
+    {
+        let mut t = Self::new();
+
+        let mut size = match t {
+            CFixedStringN::Local {
+                ref mut s,
+                ref mut len,
+            } => match f(s.as_mut_ptr() as *mut c_char, N) {
+                FillResult::Written(written) => {
+                    let checked = unsafe { check_filled(s.as_ptr() as *const u8, N, written) };
+
+                    if checked.is_ok() {
+                        s[written] = mem::MaybeUninit::new(0);
+                        *len = written;
+                    } else {
+                        s[0] = mem::MaybeUninit::new(0);
+                    }
+
+                    // `f` may have used the whole buffer, guard bytes included
+                    t.poison_unused();
+                    return checked.map(|()| t);
+                }
+                FillResult::TooSmall(needed) => {
+                    s[0] = mem::MaybeUninit::new(0);
+                    t.poison_unused();
+                    needed.max(N + 1)
+                }
+            },
+            _ => unreachable!(),
+        };
+
+        for _ in 0..MAX_RETRIES {
+            if !HEAP_BUDGET.try_grow(0, size) {
+                return Err(Error::HeapBudgetExceeded { needed: size });
+            }
+
+            let mut heap = vec![0u8; size];
+
+            match f(heap.as_mut_ptr() as *mut c_char, size) {
+                FillResult::Written(written) => {
+                    if let Err(error) = unsafe { check_filled(heap.as_ptr(), size, written) } {
+                        HEAP_BUDGET.release(size);
+                        return Err(error);
+                    }
+
+                    // The whole buffer is kept, so the budget still counts
+                    // all of it
+                    heap.truncate(written + 1);
+                    heap[written] = 0;
+
+                    let mut t = CFixedStringN::Heap {
+                        s: heap,
+                        len: written,
+                    };
+
+                    t.write_canary();
+                    return Ok(t);
+                }
+                FillResult::TooSmall(needed) => {
+                    HEAP_BUDGET.release(size);
+                    size = needed.max(size + 1);
+                }
+            }
+        }
+
+        Err(Error::CapacityExceeded { needed: size })
+    }
+
+    /// Returns a pointer for C code to write the string into, with room
+    /// for `capacity()` bytes plus the terminator. Call `set_len` with the
+    /// written length afterwards.
+    ///
+    /// A static string is copied first, as its data can't be written to.
+    /// Use `reserve` beforehand to make more room.
+    pub fn as_mut_ptr(&mut self) -> *mut c_char {
+        self.make_owned();
+
+        match *self {
+            CFixedStringN::Local { ref mut s, .. } => s.as_mut_ptr() as *mut c_char,
+            CFixedStringN::Heap { ref mut s, .. } => s.as_mut_ptr() as *mut c_char,
+            CFixedStringN::Static { .. } => unreachable!(),
+        }
+    }
+
+    /// Sets the length to what C code reported writing through
+    /// `as_mut_ptr`, and writes the terminator after it.
+    ///
+    /// The written bytes aren't scanned, and debug builds put the guard
+    /// bytes checked by `verify` back.
+    ///
+    /// # Safety
+    ///
+    /// `new_len` must be at most `capacity()`, and the first `new_len` bytes
+    /// must have been written since the last call to `as_mut_ptr`. They must
+    /// not contain zeros and must be valid UTF-8, as the safe `str` views
+    /// such as `AsRef<str>` and `Index` rely on it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string is static, as it wasn't written through
+    /// `as_mut_ptr`.
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        debug_assert!(new_len <= self.capacity());
+
+        match *self {
+            CFixedStringN::Local {
+                ref mut s,
+                ref mut len,
+            } => {
+                s[new_len] = mem::MaybeUninit::new(0);
+                *len = new_len;
+            }
+            CFixedStringN::Heap {
+                ref mut s,
+                ref mut len,
+            } => {
+                *s.as_mut_ptr().add(new_len) = 0;
+                s.set_len(new_len + 1);
+                *len = new_len;
+            }
+            CFixedStringN::Static { .. } => panic!("set_len called on a static string"),
+        }
+
+        // The callee may have written over the guard bytes
+        self.poison_unused();
+    }
+}
+
+// Only the reported bytes are read, as the rest of an inline buffer may be
+// uninitialized.
+//
+// `buf` must be valid for `size` bytes, of which `f` initialized at least
+// the first `written` if it reports a length that fits.
+unsafe fn check_filled(buf: *const u8, size: usize, written: usize) -> Result<(), Error> {
+    if written >= size {
+        return Err(Error::CapacityExceeded {
+            needed: written + 1,
+        });
+    }
+
+    let bytes = std::slice::from_raw_parts(buf, written);
+
+    if let Some(position) = bytes.iter().position(|&b| b == 0) {
+        return Err(Error::InteriorNul { position });
+    }
+
+    std::str::from_utf8(bytes)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CFixedString, STRING_SIZE};
+    use std::ptr;
+
+    // Behaves like a C function that copies `src` if it fits and reports
+    // the needed size otherwise.
+    fn copy_out(src: &[u8], dst: *mut c_char, size: usize) -> FillResult {
+        if src.len() + 1 > size {
+            return FillResult::TooSmall(src.len() + 1);
+        }
+
+        unsafe {
+            ptr::copy_nonoverlapping(src.as_ptr(), dst as *mut u8, src.len());
+        }
+
+        FillResult::Written(src.len())
+    }
+
+    #[test]
+    fn test_fill_with_retry() {
+        let t = CFixedString::fill_with_retry(|ptr, size| copy_out(b"short", ptr, size)).unwrap();
+        assert!(!t.is_allocated());
+        assert_eq!(&t.to_string(), "short");
+
+        let long = "l".repeat(STRING_SIZE * 3);
+        let mut calls = 0;
+
+        let t = CFixedString::fill_with_retry(|ptr, size| {
+            calls += 1;
+            copy_out(long.as_bytes(), ptr, size)
+        })
+        .unwrap();
+
+        assert_eq!(calls, 2);
+        assert!(t.is_allocated());
+        assert_eq!(&t.to_string(), &long);
+    }
+
+    #[test]
+    fn test_fill_with_retry_errors() {
+        let t = CFixedString::fill_with_retry(|ptr, size| copy_out(b"a\0b", ptr, size));
+        assert_eq!(t.err(), Some(Error::InteriorNul { position: 1 }));
+
+        let t = CFixedString::fill_with_retry(|ptr, size| copy_out(b"\xff", ptr, size));
+        assert_eq!(t.err(), Some(Error::InvalidUtf8 { offset: 0 }));
+
+        // Never satisfied, so the retries run out
+        let t = CFixedString::fill_with_retry(|_, size| FillResult::TooSmall(size + 1));
+        assert!(matches!(t.err(), Some(Error::CapacityExceeded { .. })));
+    }
+
+    #[test]
+    fn test_set_len() {
+        let mut t = CFixedString::from_static("old\0");
+        let ptr = t.as_mut_ptr();
+        assert!(!t.is_static());
+
+        let written = match copy_out(b"from C", ptr, t.capacity() + 1) {
+            FillResult::Written(written) => written,
+            _ => unreachable!(),
+        };
+
+        unsafe { t.set_len(written) };
+        assert_eq!(t, "from C");
+        t.verify();
+
+        let long = "c".repeat(STRING_SIZE * 2);
+        t.reserve(long.len()).unwrap();
+
+        let ptr = t.as_mut_ptr();
+        let size = t.capacity() + 1;
+        assert_eq!(
+            copy_out(long.as_bytes(), ptr, size),
+            FillResult::Written(long.len())
+        );
+
+        unsafe { t.set_len(long.len()) };
+        assert_eq!(t, long.as_str());
+        assert_eq!(t.to_bytes_with_nul().len(), long.len() + 1);
+
+        unsafe { t.set_len(4) };
+        assert_eq!(t, "cccc");
+    }
+}
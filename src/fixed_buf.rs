@@ -0,0 +1,160 @@
+use crate::{floor_char_boundary, nul_policy, Error, NulPolicy};
+use core::ffi::CStr;
+use std::str;
+
+/// The buffer handling shared by the string types that keep their contents
+/// and the terminator at the start of one byte buffer: `CStrBuf`,
+/// `CFixedStringNoAlloc`, `CStorageString` and `CFixedStringIn`.
+///
+/// Everything is written through `push`, which only writes whole `&str`s
+/// or a part of one that ends on a character boundary, so the contents are
+/// always valid UTF-8.
+pub(crate) trait FixedBuf {
+    /// The whole buffer, including room for the terminator
+    fn buf(&self) -> &[u8];
+
+    fn buf_mut(&mut self) -> &mut [u8];
+
+    fn len(&self) -> usize;
+
+    fn set_len(&mut self, len: usize);
+
+    /// Makes the buffer at least `needed` bytes long, which fails for the
+    /// ones that can't grow
+    fn grow(&mut self, needed: usize) -> Result<(), Error> {
+        Err(Error::CapacityExceeded { needed })
+    }
+
+    fn as_cstr(&self) -> &CStr {
+        unsafe { CStr::from_bytes_with_nul_unchecked(&self.buf()[..=self.len()]) }
+    }
+
+    fn as_str(&self) -> &str {
+        unsafe { str::from_utf8_unchecked(&self.buf()[..self.len()]) }
+    }
+
+    fn clear(&mut self) {
+        self.buf_mut()[0] = 0;
+        self.set_len(0);
+    }
+
+    /// Appends `s`, growing the buffer if it doesn't fit.
+    ///
+    /// When it can't grow, a `cut` write keeps what fits up to the last
+    /// whole character, otherwise the write fails and nothing is changed.
+    /// Zero bytes are handled by `NulPolicy`, a `cut` write rejected by it
+    /// keeps the part before the first of them.
+    fn push(&mut self, s: &str, cut: bool) -> Result<(), Error> {
+        let (text, replace, nul) = match s.find('\0') {
+            None => (s, false, None),
+            Some(position) => match nul_policy() {
+                NulPolicy::Reject if cut => (&s[..position], false, Some(position)),
+                NulPolicy::Reject => return Err(Error::InteriorNul { position }),
+                NulPolicy::Truncate => (&s[..position], false, None),
+                NulPolicy::Replace => (s, true, None),
+            },
+        };
+
+        // Each replaced zero byte becomes the three bytes of `U+FFFD`
+        let zeros = if replace {
+            text.bytes().filter(|&b| b == 0).count()
+        } else {
+            0
+        };
+
+        let needed = self.len() + text.len() + zeros * 2 + 1;
+
+        if needed > self.buf().len() {
+            match self.grow(needed) {
+                Err(error) if !cut => return Err(error),
+                _ => (),
+            }
+        }
+
+        let fit = if replace {
+            text.split('\0').enumerate().all(|(i, piece)| {
+                (i == 0 || self.write_within("\u{fffd}")) && self.write_within(piece)
+            })
+        } else {
+            self.write_within(text)
+        };
+
+        if !fit {
+            return Err(Error::CapacityExceeded { needed });
+        }
+
+        match nul {
+            Some(position) => Err(Error::InteriorNul { position }),
+            None => Ok(()),
+        }
+    }
+
+    // Writes as much of `text` as fits and re-terminates, returning false
+    // if it had to be cut
+    fn write_within(&mut self, text: &str) -> bool {
+        let len = self.len();
+        let room = self.buf().len() - len - 1;
+        let end = floor_char_boundary(text.as_bytes(), room);
+
+        let buf = self.buf_mut();
+        buf[len..len + end].copy_from_slice(&text.as_bytes()[..end]);
+        buf[len + end] = 0;
+
+        self.set_len(len + end);
+        end == text.len()
+    }
+}
+
+// The accessors that are the same for every `FixedBuf`, along with `Deref`
+// to `CStr` and a `fmt::Write` on top of the type's own `push_str`
+macro_rules! fixed_buf_api {
+    (impl[$($gen:tt)*] $ty:ty) => {
+        impl<$($gen)*> $ty {
+            /// Resets to an empty string
+            pub fn clear(&mut self) {
+                $crate::fixed_buf::FixedBuf::clear(self)
+            }
+
+            /// Returns the pointer to be passed down to the C code
+            pub fn as_ptr(&self) -> *const ::core::ffi::c_char {
+                $crate::fixed_buf::FixedBuf::buf(self).as_ptr() as *const ::core::ffi::c_char
+            }
+
+            /// Returns the contents as a `str`
+            pub fn as_str(&self) -> &str {
+                $crate::fixed_buf::FixedBuf::as_str(self)
+            }
+
+            /// Returns the contents as a `CStr`
+            pub fn as_cstr(&self) -> &::core::ffi::CStr {
+                $crate::fixed_buf::FixedBuf::as_cstr(self)
+            }
+
+            /// Returns the length of the string, excluding the terminator
+            pub fn len(&self) -> usize {
+                $crate::fixed_buf::FixedBuf::len(self)
+            }
+
+            /// Returns true if the string is empty
+            pub fn is_empty(&self) -> bool {
+                $crate::fixed_buf::FixedBuf::len(self) == 0
+            }
+        }
+
+        impl<$($gen)*> ::std::ops::Deref for $ty {
+            type Target = ::core::ffi::CStr;
+
+            fn deref(&self) -> &::core::ffi::CStr {
+                $crate::fixed_buf::FixedBuf::as_cstr(self)
+            }
+        }
+
+        impl<$($gen)*> ::std::fmt::Write for $ty {
+            fn write_str(&mut self, s: &str) -> ::std::fmt::Result {
+                self.push_str(s).map_err(|_| ::std::fmt::Error)
+            }
+        }
+    };
+}
+
+pub(crate) use fixed_buf_api;
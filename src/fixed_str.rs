@@ -0,0 +1,210 @@
+use crate::{CFixedString, CFixedStringN};
+use core::ffi::{c_char, CStr};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::ops;
+
+/// A borrowed view of a C string, the `str` to `CFixedString`'s `String`.
+///
+/// `CFixedString` borrows as a `CFixedStr` and both hash like `str`, so maps
+/// keyed by `CFixedString` can be searched with a `&str` or with a view of
+/// any C string without building an owned key first. A plain `&CStr` hashes
+/// differently and has to be wrapped with `from_cstr` for lookups.
+///
+/// ```
+/// use cfixed_string::{CFixedStr, CFixedString};
+/// use std::collections::HashMap;
+/// use std::ffi::CStr;
+///
+/// let mut map = HashMap::new();
+/// map.insert(CFixedString::from_str("key"), 1);
+///
+/// let key = CStr::from_bytes_with_nul(b"key\0").unwrap();
+/// assert_eq!(map.get(CFixedStr::from_cstr(key)), Some(&1));
+/// ```
+#[repr(transparent)]
+pub struct CFixedStr(CStr);
+
+impl CFixedStr {
+    /// Views `s` as a `CFixedStr`
+    pub fn from_cstr(s: &CStr) -> &CFixedStr {
+        // `CFixedStr` is a transparent wrapper, so the layout is the same
+        unsafe { &*(s as *const CStr as *const CFixedStr) }
+    }
+
+    /// Views the C string at `ptr` as a `CFixedStr`.
+    ///
+    /// # Safety
+    ///
+    /// Same as for `CStr::from_ptr`, `ptr` must point to a valid zero
+    /// terminated string that outlives `'a`.
+    pub unsafe fn from_ptr<'a>(ptr: *const c_char) -> &'a CFixedStr {
+        Self::from_cstr(CStr::from_ptr(ptr))
+    }
+
+    /// Returns the string as a `CStr`
+    pub fn as_cstr(&self) -> &CStr {
+        &self.0
+    }
+}
+
+impl ops::Deref for CFixedStr {
+    type Target = CStr;
+
+    fn deref(&self) -> &CStr {
+        &self.0
+    }
+}
+
+impl Hash for CFixedStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Like the `str` that `CFixedString` also borrows as. A view that
+        // isn't valid UTF-8 can't be equal to any `CFixedString`, so it
+        // hashes as bytes instead
+        match std::str::from_utf8(self.to_bytes()) {
+            Ok(s) => s.hash(state),
+            Err(_) => self.to_bytes().hash(state),
+        }
+    }
+}
+
+impl PartialEq for CFixedStr {
+    fn eq(&self, other: &CFixedStr) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for CFixedStr {}
+
+impl PartialOrd for CFixedStr {
+    fn partial_cmp(&self, other: &CFixedStr) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Unsigned byte by byte like `strcmp`, which is also how `str` orders
+impl Ord for CFixedStr {
+    fn cmp(&self, other: &CFixedStr) -> Ordering {
+        self.to_bytes().cmp(other.to_bytes())
+    }
+}
+
+impl ToOwned for CFixedStr {
+    type Owned = CFixedString;
+
+    /// Invalid UTF-8 is replaced like `CFixedString::to_string` does, as
+    /// the owned string has to hold valid UTF-8
+    fn to_owned(&self) -> CFixedString {
+        let mut t = CFixedString::new();
+        t.push_bytes(self.to_string_lossy().as_bytes()).unwrap();
+        t
+    }
+}
+
+impl<'a> From<&'a CStr> for &'a CFixedStr {
+    fn from(s: &'a CStr) -> Self {
+        CFixedStr::from_cstr(s)
+    }
+}
+
+impl<const N: usize> Borrow<CFixedStr> for CFixedStringN<N> {
+    fn borrow(&self) -> &CFixedStr {
+        CFixedStr::from_cstr(self)
+    }
+}
+
+impl<const N: usize> AsRef<CFixedStr> for CFixedStringN<N> {
+    fn as_ref(&self) -> &CFixedStr {
+        CFixedStr::from_cstr(self)
+    }
+}
+
+// Hashed and compared like the `str` and `CFixedStr` it borrows as, so
+// lookups with either find the same entries.
+impl<const N: usize> Hash for CFixedStringN<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        unsafe { self.as_str() }.hash(state)
+    }
+}
+
+impl<const N: usize, const M: usize> PartialEq<CFixedStringN<M>> for CFixedStringN<N> {
+    fn eq(&self, other: &CFixedStringN<M>) -> bool {
+        **self == **other
+    }
+}
+
+impl<const N: usize> Eq for CFixedStringN<N> {}
+
+impl<const N: usize, const M: usize> PartialOrd<CFixedStringN<M>> for CFixedStringN<N> {
+    fn partial_cmp(&self, other: &CFixedStringN<M>) -> Option<Ordering> {
+        Some(self.to_bytes().cmp(other.to_bytes()))
+    }
+}
+
+impl<const N: usize> Ord for CFixedStringN<N> {
+    fn cmp(&self, other: &CFixedStringN<N>) -> Ordering {
+        self.to_bytes().cmp(other.to_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_fixed_str_lookups() {
+        let long = "v".repeat(crate::STRING_SIZE * 2);
+
+        let mut map = HashMap::new();
+        map.insert(CFixedString::from_str("short"), 1);
+        map.insert(CFixedString::from_str(&long), 2);
+
+        let short = CStr::from_bytes_with_nul(b"short\0").unwrap();
+        let view = unsafe { CFixedStr::from_ptr(short.as_ptr()) };
+        assert_eq!(map.get(view), Some(&1));
+        assert_eq!(map.get(CFixedStr::from_cstr(short)), Some(&1));
+        assert_eq!(map.get("short"), Some(&1));
+        assert_eq!(map.get(long.as_str()), Some(&2));
+
+        let long = CFixedString::from_str(&long);
+        assert_eq!(map.get(Borrow::<CFixedStr>::borrow(&long)), Some(&2));
+
+        let owned: Cow<'_, CFixedStr> = Cow::Borrowed(view);
+        assert!(owned.into_owned() == CFixedString::from_str("short"));
+        assert!(CFixedStringN::<4>::from_str("short") == CFixedString::from_str("short"));
+
+        fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+            let mut state = DefaultHasher::new();
+            value.hash(&mut state);
+            state.finish()
+        }
+
+        assert_eq!(hash(&CFixedString::from_str("short")), hash("short"));
+        assert_eq!(hash(CFixedStr::from_cstr(short)), hash("short"));
+
+        let bad = CStr::from_bytes_with_nul(b"\xffkey\0").unwrap();
+        assert_eq!(CFixedStr::from_cstr(bad).to_owned(), "\u{fffd}key");
+    }
+
+    #[test]
+    fn test_strcmp_order() {
+        let mut names: Vec<CFixedString> = ["b", "\u{e5}", "ab", "a", "Z", "abc"]
+            .iter()
+            .map(CFixedString::from_str)
+            .collect();
+        names.sort();
+
+        let sorted: Vec<&str> = names.iter().map(|s| unsafe { s.as_str() }).collect();
+        assert_eq!(sorted, ["Z", "a", "ab", "abc", "b", "\u{e5}"]);
+
+        let key = CStr::from_bytes_with_nul(b"abc\0").unwrap();
+        assert_eq!(names.binary_search_by(|s| (**s).cmp(key)), Ok(3));
+        assert!(CFixedStringN::<4>::from_str("long") < CFixedString::from_str("short"));
+        let prefix = CStr::from_bytes_with_nul(b"ab\0").unwrap();
+        assert!(CFixedStr::from_cstr(key) > CFixedStr::from_cstr(prefix));
+    }
+}
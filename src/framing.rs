@@ -0,0 +1,96 @@
+use crate::{CFixedStringN, Error};
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Writes the string with a length-prefixed framing: the length as a
+    /// little-endian `u32` followed by the bytes, without the terminator.
+    ///
+    /// The result can be read back with `read_from`.
+    pub fn write_to<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let bytes = self.to_bytes();
+        let len = u32::try_from(bytes.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "string too long"))?;
+
+        w.write_all(&len.to_le_bytes())?;
+        w.write_all(bytes)
+    }
+
+    /// Reads a string written by `write_to`.
+    ///
+    /// Short strings are read straight into the inline buffer. Data with
+    /// zero bytes or invalid UTF-8 is rejected with `InvalidData`.
+    pub fn read_from<R: Read>(mut r: R) -> io::Result<Self> {
+        let mut len = [0; 4];
+        r.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len) as usize;
+
+        let mut inline = [0; N];
+        let mut heap = Vec::new();
+
+        let bytes = if len < N {
+            r.read_exact(&mut inline[..len])?;
+            &inline[..len]
+        } else {
+            // Grows with the data actually received so a bogus length
+            // can't trigger a huge allocation up front.
+            r.take(len as u64).read_to_end(&mut heap)?;
+
+            if heap.len() != len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+
+            &heap[..]
+        };
+
+        if let Some(position) = bytes.iter().position(|&b| b == 0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                Error::InteriorNul { position },
+            ));
+        }
+
+        std::str::from_utf8(bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, Error::from(e)))?;
+
+        let mut s = Self::new();
+        s.push_bytes(bytes).map_err(io::Error::other)?;
+
+        Ok(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CFixedString, STRING_SIZE};
+
+    #[test]
+    fn test_round_trip() {
+        let long = "ab".repeat(STRING_SIZE);
+        let mut out = Vec::new();
+
+        CFixedString::from_str("short").write_to(&mut out).unwrap();
+        CFixedString::from_str(&long).write_to(&mut out).unwrap();
+
+        assert_eq!(&out[..9], b"\x05\x00\x00\x00short");
+
+        let mut r = &out[..];
+        let short = CFixedString::read_from(&mut r).unwrap();
+        let heap = CFixedString::read_from(&mut r).unwrap();
+
+        assert!(!short.is_allocated());
+        assert_eq!(&short.to_string(), "short");
+        assert!(heap.is_allocated());
+        assert_eq!(&heap.to_string(), &long);
+    }
+
+    #[test]
+    fn test_bad_input() {
+        let err = CFixedString::read_from(&b"\x03\x00\x00\x00a\0b"[..]).err();
+        assert_eq!(err.unwrap().kind(), io::ErrorKind::InvalidData);
+
+        let err = CFixedString::read_from(&b"\xff\xff\x00\x00abc"[..]).err();
+        assert_eq!(err.unwrap().kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
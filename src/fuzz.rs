@@ -0,0 +1,91 @@
+use crate::CFixedStringN;
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+// Characters that tend to trip up byte length and boundary handling: each
+// encoded length at its limits, combining marks, joiners and the BOM.
+const TRICKY: [char; 12] = [
+    '\u{7f}',
+    '\u{80}',
+    '\u{e5}',
+    '\u{301}',
+    '\u{7ff}',
+    '\u{800}',
+    '\u{200d}',
+    '\u{feff}',
+    '\u{fffd}',
+    '\u{ffff}',
+    '\u{1f600}',
+    '\u{10ffff}',
+];
+
+/// Generates strings that fit inline as well as ones longer than the
+/// inline buffer, mixing ASCII with multi-byte characters. Zero characters
+/// are never generated, so the values can be passed to C as is.
+impl<'a, const N: usize> Arbitrary<'a> for CFixedStringN<N> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let heap = u.arbitrary::<bool>()?;
+        let target = if heap {
+            u.int_in_range(N..=N * 4)?
+        } else {
+            u.int_in_range(0..=N - 1)?
+        };
+
+        let mut t = Self::new();
+        let mut buf = [0; 4];
+
+        while t.to_bytes().len() < target && !u.is_empty() {
+            let c = match u.int_in_range(0..=3)? {
+                0 | 1 => char::from(u.int_in_range(1..=0x7f)?),
+                2 => *u.choose(&TRICKY)?,
+                _ => match u.arbitrary::<char>()? {
+                    '\0' => continue,
+                    c => c,
+                },
+            };
+
+            // Inline values stay inline, heap ones may overshoot the target
+            if !heap && t.to_bytes().len() + c.len_utf8() > target {
+                break;
+            }
+
+            t.push_bytes(c.encode_utf8(&mut buf).as_bytes())
+                .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+        }
+
+        Ok(t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CFixedString, STRING_SIZE};
+
+    #[test]
+    fn test_arbitrary() {
+        let data: Vec<u8> = (0..16 * 1024).map(|i| (i * 7919 % 251) as u8).collect();
+        let (mut inline, mut heap, mut multi_byte) = (0, 0, 0);
+
+        for start in 0..64 {
+            let mut u = Unstructured::new(&data[start * 97..]);
+            let t = CFixedString::arbitrary(&mut u).unwrap();
+            let bytes = t.to_bytes();
+
+            assert!(std::str::from_utf8(bytes).is_ok());
+            assert!(!bytes.contains(&0));
+            assert_eq!(t.is_allocated(), bytes.len() >= STRING_SIZE);
+
+            if t.is_allocated() {
+                heap += 1;
+            } else {
+                inline += 1;
+            }
+
+            if !bytes.is_ascii() {
+                multi_byte += 1;
+            }
+        }
+
+        assert!(inline > 0 && heap > 0 && multi_byte > 0);
+    }
+}
@@ -0,0 +1,77 @@
+use std::fmt;
+
+const BYTES_PER_LINE: usize = 16;
+
+/// Display adapter returned by `CFixedString::hex_dump`.
+///
+/// Renders the bytes as an offset/hex/ASCII dump, 16 bytes per line.
+pub struct HexDump<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> HexDump<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        HexDump { bytes }
+    }
+}
+
+impl<'a> fmt::Display for HexDump<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (line, chunk) in self.bytes.chunks(BYTES_PER_LINE).enumerate() {
+            if line != 0 {
+                f.write_str("\n")?;
+            }
+
+            write!(f, "{:08x} ", line * BYTES_PER_LINE)?;
+
+            for i in 0..BYTES_PER_LINE {
+                if i % 8 == 0 {
+                    f.write_str(" ")?;
+                }
+
+                match chunk.get(i) {
+                    Some(b) => write!(f, "{:02x} ", b)?,
+                    None => f.write_str("   ")?,
+                }
+            }
+
+            f.write_str(" |")?;
+
+            for &b in chunk {
+                let c = if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                };
+
+                write!(f, "{}", c)?;
+            }
+
+            f.write_str("|")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Debug for HexDump<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let dump = HexDump::new(b"hello, C world!\0\x01").to_string();
+
+        assert_eq!(
+            dump,
+            "00000000  68 65 6c 6c 6f 2c 20 43  20 77 6f 72 6c 64 21 00  |hello, C world!.|\n\
+             00000010  01                                                |.|"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{CFixedStringN, RetainedCString};
+use core::ffi::{c_char, c_void};
+use std::sync::{Arc, Condvar, Mutex};
+
+struct LoanState {
+    returned: Mutex<bool>,
+    cond: Condvar,
+}
+
+impl LoanState {
+    fn give_back(&self) {
+        *self.returned.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        self.cond.notify_all();
+    }
+
+    fn wait(&self) {
+        let mut returned = self.returned.lock().unwrap_or_else(|e| e.into_inner());
+
+        while !*returned {
+            returned = self.cond.wait(returned).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+}
+
+/// A pointer to a `CFixedString` lent out by `CFixedString::lend_to`.
+///
+/// The string is kept alive until the loan is dropped, or until the release
+/// function of `into_retained` is called, even if that happens on another
+/// thread.
+pub struct Loan {
+    ptr: *const c_char,
+    state: Arc<LoanState>,
+}
+
+// The pointer is only read through, and `lend_to` keeps the string borrowed
+// until the loan is given back.
+unsafe impl Send for Loan {}
+unsafe impl Sync for Loan {}
+
+impl Loan {
+    /// Returns the pointer to the lent string
+    pub fn as_ptr(&self) -> *const c_char {
+        self.ptr
+    }
+
+    /// Converts the loan into a pointer and release function for C code,
+    /// such as a thread started by a C library.
+    ///
+    /// The loan is given back when `release` is called with `ctx`, which
+    /// must happen exactly once.
+    pub fn into_retained(self) -> RetainedCString {
+        // Giving back is now up to `release_loan`, which gets our reference
+        // to the state
+        let this = std::mem::ManuallyDrop::new(self);
+        let ptr = this.ptr;
+        let state = unsafe { std::ptr::read(&this.state) };
+
+        RetainedCString {
+            ptr,
+            release: release_loan,
+            ctx: Arc::into_raw(state) as *mut c_void,
+        }
+    }
+}
+
+impl Drop for Loan {
+    fn drop(&mut self) {
+        self.state.give_back();
+    }
+}
+
+unsafe extern "C" fn release_loan(ctx: *mut c_void) {
+    Arc::from_raw(ctx as *const LoanState).give_back();
+}
+
+// Waits on unwinding too, as the loan may already be on another thread.
+struct WaitOnDrop(Arc<LoanState>);
+
+impl Drop for WaitOnDrop {
+    fn drop(&mut self) {
+        self.0.wait();
+    }
+}
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Lends the string's pointer to code that may keep using it after `f`
+    /// returns, such as a worker thread or an asynchronous C callback.
+    ///
+    /// `f` gets a `Loan` it can move anywhere. `lend_to` doesn't return,
+    /// and so the string can't be modified or dropped, until the loan is
+    /// dropped or released. A loan that is never given back makes this
+    /// block forever.
+    pub fn lend_to<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(Loan) -> R,
+    {
+        let state = Arc::new(LoanState {
+            returned: Mutex::new(false),
+            cond: Condvar::new(),
+        });
+
+        let _wait = WaitOnDrop(Arc::clone(&state));
+
+        f(Loan {
+            ptr: self.as_ptr(),
+            state,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CFixedString;
+    use core::ffi::CStr;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_lend_to_thread() {
+        let name = CFixedString::from_str("worker-1");
+        let (tx, rx) = mpsc::channel();
+
+        let handle = name.lend_to(|loan| {
+            thread::spawn(move || {
+                // Still valid after `lend_to` would otherwise have returned
+                thread::sleep(Duration::from_millis(20));
+                let s = unsafe { CStr::from_ptr(loan.as_ptr()) };
+                tx.send(s.to_bytes().to_vec()).unwrap();
+            })
+        });
+
+        // The thread gave the loan back, so it has sent already
+        assert_eq!(rx.try_recv().unwrap(), b"worker-1");
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_lend_to_retained() {
+        let name = CFixedString::from_str("callback");
+
+        // Stands in for C code that gets the raw parts on another thread
+        struct ForC(RetainedCString);
+        unsafe impl Send for ForC {}
+
+        name.lend_to(|loan| {
+            let raw = ForC(loan.into_retained());
+
+            thread::spawn(move || unsafe {
+                let raw = raw;
+                assert_eq!(CStr::from_ptr(raw.0.ptr).to_bytes(), b"callback");
+                (raw.0.release)(raw.0.ctx);
+            });
+        });
+    }
+}
@@ -1,63 +1,259 @@
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+
 use std::borrow::{Borrow, Cow};
-use std::ffi::{CStr, CString};
-use std::mem::MaybeUninit;
-use std::os::raw::c_char;
+use std::ffi::{CString, OsStr, OsString};
+use std::iter::FromIterator;
 use std::ptr;
 use std::{fmt, mem, ops};
 
-const STRING_SIZE: usize = 512;
+mod alloc;
+#[cfg(feature = "rkyv")]
+mod archive;
+mod argv;
+mod ascii;
+mod assert;
+mod budget;
+mod buf;
+mod canary;
+mod cow;
+mod edit;
+mod error;
+mod file;
+mod fill;
+mod fixed_buf;
+mod fixed_str;
+mod framing;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
+mod hex_dump;
+mod lend;
+mod log;
+mod map;
+mod mark;
+mod no_alloc;
+mod nul;
+mod num;
+pub mod path;
+mod pattern;
+#[cfg(feature = "pyo3")]
+mod python;
+mod raw;
+#[cfg(feature = "serde")]
+mod serialize;
+mod shm;
+mod stable;
+mod storage;
+mod stream;
+mod utf16;
+#[cfg(feature = "ufmt")]
+mod uwrite;
+mod wasm;
+#[cfg(feature = "unicode-width")]
+mod width;
+mod writer;
+
+#[cfg(feature = "allocator_api")]
+pub use crate::alloc::AllocatorApi;
+pub use crate::alloc::{Alloc, CFixedStringIn, Global};
+pub use crate::argv::CArgv;
+pub use crate::ascii::AsciiCaseInsensitive;
+pub use crate::budget::{heap_usage, set_heap_budget, BudgetPolicy};
+pub use crate::buf::CStrBuf;
+pub use crate::cow::CFixedCow;
+pub use crate::error::{CFixedStringError, Error, IntoStringError};
+pub use crate::fill::FillResult;
+pub use crate::fixed_str::CFixedStr;
+
+#[doc(hidden)]
+pub use crate::assert::__assert_c_eq;
+use crate::budget::HEAP_BUDGET;
+pub use crate::hex_dump::HexDump;
+pub use crate::lend::Loan;
+pub use crate::log::__log_c;
+pub use crate::map::{CFixedStringMap, CFixedStringSet};
+pub use crate::mark::Mark;
+pub use crate::no_alloc::CFixedStringNoAlloc;
+pub use crate::nul::{nul_policy, set_nul_policy, NulPolicy};
+pub use crate::pattern::{Pattern, Split, SplitTerminated};
+pub use crate::raw::RetainedCString;
+pub use crate::shm::ShmString;
+pub use crate::stable::StableCString;
+pub use crate::storage::{CStorageString, Storage};
+pub use crate::stream::IoWriter;
+pub use crate::writer::TryWriter;
+
+// The C types used in the signatures, so callers don't have to pick between
+// `core::ffi`, `std::ffi` and `std::os::raw`. They are the same types.
+pub use core::ffi::{c_char, CStr};
+
+const STRING_SIZE: usize = string_size();
+
+/// Picks the inline buffer size, including the terminator.
+///
+/// The `CFIXED_STRING_SIZE` environment variable at build time takes
+/// precedence, then the largest enabled `size-*` feature, then 512.
+const fn string_size() -> usize {
+    match option_env!("CFIXED_STRING_SIZE") {
+        Some(size) => parse_size(size),
+        None if cfg!(feature = "size-2048") => 2048,
+        None if cfg!(feature = "size-1024") => 1024,
+        None if cfg!(feature = "size-256") => 256,
+        None if cfg!(feature = "size-128") => 128,
+        None => 512,
+    }
+}
+
+const fn parse_size(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut size = 0;
+    let mut i = 0;
+
+    assert!(!bytes.is_empty(), "CFIXED_STRING_SIZE is empty");
+
+    while i < bytes.len() {
+        assert!(
+            bytes[i].is_ascii_digit(),
+            "CFIXED_STRING_SIZE must be a decimal number"
+        );
+
+        size = size * 10 + (bytes[i] - b'0') as usize;
+        i += 1;
+    }
+
+    assert!(size >= 2, "CFIXED_STRING_SIZE must be at least 2");
+    size
+}
 
 /// This is a C String abstractions that presents a CStr like
 /// interface for interop purposes but tries to be little nicer
 /// by avoiding heap allocations if the string is within the
-/// generous bounds (512 bytes) of the statically sized buffer.
+/// generous bounds (512 bytes by default, see the `size-*` features)
+/// of the statically sized buffer.
 /// Strings over this limit will be heap allocated, but the
 /// interface outside of this abstraction remains the same.
-pub enum CFixedString {
+///
+/// Use `CFixedStringN` directly to pick a different inline size, such as a
+/// small one for audio threads or a large one for paths.
+pub type CFixedString = CFixedStringN<STRING_SIZE>;
+
+/// A `CFixedString` with an inline buffer of `N` bytes, including the
+/// terminator.
+///
+/// ```
+/// use cfixed_string::CFixedStringN;
+///
+/// let name = CFixedStringN::<32>::from_str("short name");
+/// assert!(!name.is_allocated());
+/// ```
+///
+/// Once on the heap the buffer grows by doubling, so building a long string
+/// with many small appends stays linear.
+///
+/// Every write keeps the contents valid UTF-8, which the `str` views such
+/// as `AsRef<str>` and `Index` rely on.
+#[allow(clippy::large_enum_variant)]
+pub enum CFixedStringN<const N: usize> {
+    /// Only the `len` bytes of the string and the terminator after them
+    /// are initialized
     Local {
-        s: [c_char; STRING_SIZE],
-        len: usize,
-    },
-    Heap {
-        s: CString,
+        s: [mem::MaybeUninit<c_char>; N],
         len: usize,
     },
+    /// `s` holds the `len` bytes of the string followed by the terminator
+    Heap { s: Vec<u8>, len: usize },
+    /// Borrowed from constant data without a copy, the first change copies
+    /// it into the inline buffer or the heap
+    Static { s: &'static CStr },
 }
 
-impl CFixedString {
+impl<const N: usize> CFixedStringN<N> {
+    /// The longest string, excluding the terminator, that fits the inline
+    /// buffer without a heap allocation
+    pub const INLINE_CAPACITY: usize = N - 1;
+
+    // Checked in `new` so a buffer without room for the terminator fails to
+    // compile
+    const HAS_TERMINATOR: () = assert!(N > 0, "the inline buffer needs room for the terminator");
+
     /// Creates an empty CFixedString, this is intended to be
     /// used with write! or the `fmt::Write` trait
     pub fn new() -> Self {
-        let data: [MaybeUninit<c_char>; STRING_SIZE] =
-            unsafe { MaybeUninit::uninit().assume_init() };
-
-        CFixedString::Local {
-            s: unsafe { std::mem::transmute(data) },
-            len: 0,
-        }
+        #[allow(clippy::let_unit_value)]
+        let () = Self::HAS_TERMINATOR;
+
+        // Debug builds initialize the whole buffer in `poison_unused` so
+        // that the unused capacity can be inspected through `hex_dump`,
+        // release builds only write the terminator
+        let mut s = [mem::MaybeUninit::uninit(); N];
+        s[0] = mem::MaybeUninit::new(0);
+
+        let mut t = CFixedStringN::Local { s, len: 0 };
+        t.poison_unused();
+        t
     }
 
     /// Create from str
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str<S: AsRef<str>>(s: S) -> Self {
         Self::from(s.as_ref())
     }
 
+    /// Creates from `s`, failing with the position of its first zero byte
+    /// instead of following `NulPolicy`, or if the heap budget rejects it.
+    ///
+    /// `TryFrom<&str>` is already taken by the infallible `From<&str>`, this
+    /// is also what `str::parse` uses.
+    pub fn try_from_str(s: &str) -> Result<Self, Error> {
+        check_no_nul(s.as_bytes())?;
+        Self::copied(s.as_bytes())
+    }
+
+    /// Uses a constant string as is if it ends in a zero byte, such as
+    /// `"name\0"`, and copies it like `from_str` otherwise.
+    pub fn from_static(s: &'static str) -> Self {
+        match CStr::from_bytes_with_nul(s.as_bytes()) {
+            Ok(s) => CFixedStringN::Static { s },
+            Err(_) => Self::from(s),
+        }
+    }
+
     /// Returns the pointer to be passed down to the C code
     pub fn as_ptr(&self) -> *const c_char {
         match *self {
-            CFixedString::Local { ref s, .. } => s.as_ptr(),
-            CFixedString::Heap { ref s, .. } => s.as_ptr(),
+            CFixedStringN::Local { ref s, .. } => s.as_ptr() as *const c_char,
+            CFixedStringN::Heap { ref s, .. } => s.as_ptr() as *const c_char,
+            CFixedStringN::Static { s } => s.as_ptr(),
         }
     }
 
     /// Returns true if the string has been heap allocated
     pub fn is_allocated(&self) -> bool {
+        matches!(*self, CFixedStringN::Heap { .. })
+    }
+
+    /// Returns true if the string is borrowed from constant data
+    pub fn is_static(&self) -> bool {
+        matches!(*self, CFixedStringN::Static { .. })
+    }
+
+    /// Returns how long the string can get, excluding the terminator,
+    /// before the next append needs a new allocation
+    pub fn capacity(&self) -> usize {
         match *self {
-            CFixedString::Local { .. } => false,
-            _ => true,
+            CFixedStringN::Local { .. } => Self::INLINE_CAPACITY,
+            CFixedStringN::Heap { ref s, .. } => s.capacity() - 1,
+            CFixedStringN::Static { s } => s.to_bytes().len(),
         }
     }
 
+    /// Returns how many bytes can be appended before the next allocation.
+    ///
+    /// For an inline string this tells whether a planned write will move
+    /// it to the heap.
+    pub fn remaining_capacity(&self) -> usize {
+        self.capacity() - self.to_bytes().len()
+    }
+
     /// Converts a `CFixedString` into a `Cow<str>`.
     ///
     /// This function will calculate the length of this string (which normally
@@ -65,114 +261,999 @@ impl CFixedString {
     /// resulting slice as a `Cow<str>`, replacing any invalid UTF-8 sequences
     /// with `U+FFFD REPLACEMENT CHARACTER`. If there are no invalid UTF-8
     /// sequences, this will merely return a borrowed slice.
-    pub fn to_string(&self) -> Cow<str> {
+    #[allow(clippy::inherent_to_string_shadow_display)]
+    pub fn to_string(&self) -> Cow<'_, str> {
         String::from_utf8_lossy(self.to_bytes())
     }
 
+    /// Returns the string as a `Cow<CStr>`.
+    ///
+    /// This is always borrowed today, but gives APIs that accept C strings
+    /// from several sources a single type to take.
+    pub fn as_cow_cstr(&self) -> Cow<'_, CStr> {
+        Cow::Borrowed(self)
+    }
+
+    /// Converts into a `String`, failing instead of replacing invalid UTF-8
+    /// like the `From` conversion does.
+    ///
+    /// On failure the original string is handed back inside the error.
+    ///
+    /// This is a method rather than `TryFrom<CFixedStringN<N>> for String`,
+    /// as the standard library already derives a `TryFrom` from the `From`
+    /// conversion and the two impls would conflict.
+    #[allow(clippy::result_large_err)]
+    pub fn into_string(mut self) -> Result<String, IntoStringError<N>> {
+        if let Err(error) = std::str::from_utf8(self.to_bytes()) {
+            return Err(IntoStringError { inner: self, error });
+        }
+
+        Ok(match self {
+            CFixedStringN::Heap { .. } => unsafe {
+                let mut bytes = self.take_heap();
+                bytes.pop();
+                String::from_utf8_unchecked(bytes)
+            },
+            ref local => unsafe { local.as_str() }.to_owned(),
+        })
+    }
+
     /// Convert back to str. Unsafe as it uses `from_utf8_unchecked`
+    ///
+    /// # Safety
+    ///
+    /// The contents of the string must be valid UTF-8.
     pub unsafe fn as_str(&self) -> &str {
         use std::slice;
         use std::str;
 
         match *self {
-            CFixedString::Local { ref s, len } => {
+            CFixedStringN::Local { ref s, len } => {
                 str::from_utf8_unchecked(slice::from_raw_parts(s.as_ptr() as *const u8, len))
             }
-            CFixedString::Heap { ref s, len } => {
-                str::from_utf8_unchecked(slice::from_raw_parts(s.as_ptr() as *const u8, len))
+            CFixedStringN::Heap { ref s, len } => str::from_utf8_unchecked(&s[..len]),
+            CFixedStringN::Static { s } => str::from_utf8_unchecked(s.to_bytes()),
+        }
+    }
+
+    /// Appends the `Display` output of `value`, for building strings with
+    /// plain method calls instead of `write!`
+    pub fn write_display(&mut self, value: &impl fmt::Display) -> Result<(), Error> {
+        self.try_write_fmt(format_args!("{}", value))
+    }
+
+    /// Appends the `Display` output of each item with `sep` in between,
+    /// such as a comma-separated list of names.
+    pub fn write_joined<I>(&mut self, iter: I, sep: &str) -> Result<(), Error>
+    where
+        I: IntoIterator,
+        I::Item: fmt::Display,
+    {
+        for (i, item) in iter.into_iter().enumerate() {
+            if i != 0 {
+                self.push_bytes(sep.as_bytes())?;
+            }
+
+            self.write_display(&item)?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates a string from the `Display` output of each item with `sep`
+    /// in between, such as a colon-separated search path, see
+    /// `write_joined`.
+    pub fn join<I>(iter: I, sep: &str) -> Result<Self, Error>
+    where
+        I: IntoIterator,
+        I::Item: fmt::Display,
+    {
+        let mut t = Self::new();
+        t.write_joined(iter, sep)?;
+        Ok(t)
+    }
+
+    /// Appends `s` with the XML/HTML special characters `&<>"'` escaped
+    pub fn push_xml_escaped(&mut self, s: &str) -> Result<(), Error> {
+        let mut start = 0;
+
+        for (i, b) in s.bytes().enumerate() {
+            let escaped: &[u8] = match b {
+                b'&' => b"&amp;",
+                b'<' => b"&lt;",
+                b'>' => b"&gt;",
+                b'"' => b"&quot;",
+                b'\'' => b"&#39;",
+                _ => continue,
+            };
+
+            self.push_bytes(&s.as_bytes()[start..i])?;
+            self.push_bytes(escaped)?;
+            start = i + 1;
+        }
+
+        self.push_bytes(&s.as_bytes()[start..])
+    }
+
+    /// Appends the contents of another `CFixedString`, copying its bytes
+    /// directly without going through `str`
+    pub fn push_cfixed<const M: usize>(&mut self, other: &CFixedStringN<M>) -> Result<(), Error> {
+        self.push_bytes(other.to_bytes())
+    }
+
+    /// Appends a chunk of UTF-8 input that may end in the middle of a
+    /// character, such as data read from a socket.
+    ///
+    /// Returns the number of bytes consumed. An incomplete sequence at the
+    /// end of `bytes` is left unconsumed, so the caller should keep those
+    /// bytes and pass them again in front of the next chunk. Invalid
+    /// sequences are replaced with `U+FFFD REPLACEMENT CHARACTER`.
+    pub fn push_bytes_utf8_partial(&mut self, bytes: &[u8]) -> usize {
+        let mut consumed = 0;
+
+        while consumed < bytes.len() {
+            let rest = &bytes[consumed..];
+
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    if self.push_bytes(valid.as_bytes()).is_err() {
+                        break;
+                    }
+
+                    consumed = bytes.len();
+                }
+                Err(e) => {
+                    let valid = e.valid_up_to();
+
+                    if self.push_bytes(&rest[..valid]).is_err() {
+                        break;
+                    }
+
+                    consumed += valid;
+
+                    match e.error_len() {
+                        Some(invalid) => {
+                            if self.push_bytes("\u{fffd}".as_bytes()).is_err() {
+                                break;
+                            }
+
+                            consumed += invalid;
+                        }
+                        // incomplete sequence at the end of the input
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        consumed
+    }
+
+    /// Appends raw bytes, moving the string to the heap if they don't fit.
+    ///
+    /// Heap growth is checked against the budget from `set_heap_budget`.
+    pub(crate) fn push_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        if let CFixedStringN::Static { s } = *self {
+            *self = Self::copied(s.to_bytes())?;
+        }
+
+        let bytes = match check_no_nul(bytes) {
+            Ok(()) => bytes,
+            Err(error) => match nul_policy() {
+                NulPolicy::Reject => return Err(error),
+                NulPolicy::Truncate => &bytes[..bytes.iter().position(|&b| b == 0).unwrap()],
+                NulPolicy::Replace => {
+                    for (i, piece) in bytes.split(|&b| b == 0).enumerate() {
+                        if i != 0 {
+                            self.push_bytes("\u{fffd}".as_bytes())?;
+                        }
+
+                        self.push_bytes(piece)?;
+                    }
+
+                    return Ok(());
+                }
+            },
+        };
+
+        let cur_len = self.to_bytes().len();
+        let len = cur_len + bytes.len();
+
+        // Strings that were shortened after moving to the heap stay there
+        // and reuse the spare room
+        if len < N {
+            if let CFixedStringN::Local {
+                s: ref mut ls,
+                len: ref mut lslen,
+            } = *self
+            {
+                unsafe {
+                    let ptr = ls.as_mut_ptr() as *mut u8;
+                    ptr::copy_nonoverlapping(bytes.as_ptr(), ptr.add(cur_len), bytes.len());
+                    *ptr.add(len) = 0;
+                }
+
+                *lslen = len;
+                self.write_canary();
+                return Ok(());
+            }
+        }
+
+        if len > self.capacity() {
+            let grown = match *self {
+                CFixedStringN::Heap { ref s, .. } => s.capacity() * 2,
+                _ => N * 2,
+            };
+
+            // Doubling keeps appends amortized linear, the exact size is the
+            // fallback when the doubled one would go over the budget
+            if !self.move_to_heap(grown.max(len + 1)) && !self.move_to_heap(len + 1) {
+                return match HEAP_BUDGET.policy() {
+                    BudgetPolicy::Truncate => {
+                        let end = floor_char_boundary(bytes, self.remaining_capacity());
+                        self.push_bytes(&bytes[..end])
+                    }
+                    _ => Err(Error::HeapBudgetExceeded { needed: len + 1 }),
+                };
+            }
+        }
+
+        match *self {
+            CFixedStringN::Heap {
+                ref mut s,
+                len: ref mut slen,
+            } => {
+                s.truncate(cur_len);
+                s.extend_from_slice(bytes);
+                s.push(0);
+                *slen = len;
+            }
+            _ => unreachable!(),
+        }
+
+        self.write_canary();
+        Ok(())
+    }
+
+    /// Moves the string into a heap buffer of `size` bytes, including the
+    /// terminator, returning false if that would go over the budget.
+    fn move_to_heap(&mut self, size: usize) -> bool {
+        let old_size = match *self {
+            CFixedStringN::Heap { ref s, .. } => s.capacity(),
+            _ => 0,
+        };
+
+        if !HEAP_BUDGET.try_grow(old_size, size) {
+            return false;
+        }
+
+        // Updating an existing heap string in place keeps `Drop` from
+        // releasing the old size, which `try_grow` already accounted for.
+        match *self {
+            CFixedStringN::Heap { ref mut s, .. } => s.reserve_exact(size - s.len()),
+            _ => {
+                let mut heap = Vec::with_capacity(size);
+                heap.extend_from_slice(self.to_bytes_with_nul());
+
+                let len = self.to_bytes().len();
+                *self = CFixedStringN::Heap { s: heap, len };
+            }
+        }
+
+        self.write_canary();
+        true
+    }
+
+    /// Makes room for at least `additional` more bytes, so that appending
+    /// them doesn't need another allocation.
+    ///
+    /// Moving to the heap is checked against the budget from
+    /// `set_heap_budget`, and fails with `HeapBudgetExceeded` unless the
+    /// policy allows going over it.
+    pub fn reserve(&mut self, additional: usize) -> Result<(), Error> {
+        if additional <= self.remaining_capacity() {
+            return Ok(());
+        }
+
+        let size = self.to_bytes().len() + additional + 1;
+
+        if self.move_to_heap(size) {
+            Ok(())
+        } else {
+            Err(Error::HeapBudgetExceeded { needed: size })
+        }
+    }
+
+    /// Creates an empty string with room for `capacity` bytes, excluding
+    /// the terminator.
+    ///
+    /// A capacity that doesn't fit inline goes straight to a heap buffer of
+    /// that size, instead of spilling and reallocating while it's filled.
+    /// Fails with `HeapBudgetExceeded` like `reserve`.
+    pub fn with_capacity(capacity: usize) -> Result<Self, Error> {
+        let mut t = Self::new();
+        t.reserve(capacity)?;
+        Ok(t)
+    }
+
+    /// Moves an inline or static string to the heap, so its pointer
+    /// survives the value being moved.
+    ///
+    /// The pointer stays valid until the string is changed in a way that
+    /// needs a bigger buffer. Fails with `HeapBudgetExceeded` like
+    /// `reserve`.
+    pub fn make_heap(&mut self) -> Result<(), Error> {
+        if self.is_allocated() {
+            return Ok(());
+        }
+
+        let size = self.to_bytes().len() + 1;
+
+        if self.move_to_heap(size) {
+            Ok(())
+        } else {
+            Err(Error::HeapBudgetExceeded { needed: size })
+        }
+    }
+
+    /// Moves the string to the heap, see `make_heap`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the heap budget rejects the allocation.
+    pub fn into_heap(mut self) -> Self {
+        self.make_heap().unwrap();
+        self
+    }
+
+    /// Frees the unused heap capacity, which stays around after a heap
+    /// string is shortened.
+    ///
+    /// A heap string that fits the inline buffer again is moved back into
+    /// it and its allocation is freed, longer ones get a heap buffer of
+    /// their exact size. Inline and static strings are left as they are.
+    pub fn shrink_to_fit(&mut self) {
+        match *self {
+            CFixedStringN::Heap { ref s, len } if len < N => {
+                let mut t = Self::new();
+
+                if let CFixedStringN::Local {
+                    s: ref mut dst,
+                    len: ref mut dst_len,
+                } = t
+                {
+                    for (d, &b) in dst.iter_mut().zip(&s[..=len]) {
+                        *d = mem::MaybeUninit::new(b as c_char);
+                    }
+
+                    *dst_len = len;
+                }
+
+                t.write_canary();
+
+                // Dropping the heap string releases it from the budget
+                *self = t;
+            }
+            CFixedStringN::Heap { ref mut s, .. } => {
+                let old_size = s.capacity();
+                s.shrink_to_fit();
+                HEAP_BUDGET.release(old_size - s.capacity());
+                self.write_canary();
+            }
+            _ => (),
+        }
+    }
+
+    /// Formats `args` into a new string, as `format!` does for `String`.
+    ///
+    /// The output is measured first, so a result that won't fit inline is
+    /// written straight into a heap buffer of the right size instead of
+    /// being moved there part way through.
+    pub fn from_fmt(args: fmt::Arguments<'_>) -> Result<Self, Error> {
+        struct Measure(usize);
+
+        impl fmt::Write for Measure {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                self.0 += s.len();
+                Ok(())
+            }
+        }
+
+        let mut measure = Measure(0);
+
+        match args.as_str() {
+            Some(s) => measure.0 = s.len(),
+            None => fmt::write(&mut measure, args).map_err(|_| Error::Format)?,
+        }
+
+        let mut t = Self::new();
+        t.reserve(measure.0)?;
+        t.try_write_fmt(args)?;
+        Ok(t)
+    }
+
+    /// Copies `bytes` into a new string that owns its contents
+    fn copied(bytes: &[u8]) -> Result<Self, Error> {
+        let mut t = Self::new();
+        t.push_bytes(bytes)?;
+        Ok(t)
+    }
+
+    /// Takes the heap buffer, including the terminator, out of the string
+    /// and stops counting it against the budget.
+    fn take_heap(&mut self) -> Vec<u8> {
+        match *self {
+            CFixedStringN::Heap {
+                ref mut s,
+                ref mut len,
+            } => {
+                HEAP_BUDGET.release(s.capacity());
+                *len = 0;
+                mem::take(s)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Shortens the string to `new_len` bytes and re-terminates it.
+    ///
+    /// `new_len` must not be past the end. Every operation that shortens
+    /// the string goes through here, so a `new_len` inside a UTF-8 sequence
+    /// is moved back to the start of it to keep the contents valid.
+    pub(crate) fn truncate_bytes(&mut self, new_len: usize) {
+        let new_len = floor_char_boundary(self.to_bytes(), new_len);
+
+        match *self {
+            CFixedStringN::Local {
+                ref mut s,
+                ref mut len,
+            } => {
+                debug_assert!(new_len <= *len);
+                s[new_len] = mem::MaybeUninit::new(0);
+                *len = new_len;
+            }
+            CFixedStringN::Heap {
+                ref mut s,
+                ref mut len,
+            } => {
+                debug_assert!(new_len <= *len);
+
+                // The buffer is kept for later appends
+                s.truncate(new_len);
+                s.push(0);
+                *len = new_len;
+            }
+            CFixedStringN::Static { s } => {
+                // Panics like `From<&str>` if the budget rejects the copy
+                *self = Self::copied(&s.to_bytes()[..new_len]).unwrap();
+            }
+        }
+
+        self.poison_unused();
+    }
+
+    /// Shortens the string to at most `max_bytes` bytes, excluding the
+    /// terminator, without cutting a UTF-8 sequence in half.
+    ///
+    /// This is for C APIs with hard byte limits on UTF-8 text, such as
+    /// fixed size label fields. Shorter strings are left as they are.
+    pub fn truncate_to_char_boundary(&mut self, max_bytes: usize) {
+        let bytes = self.to_bytes();
+
+        if max_bytes < bytes.len() {
+            let end = floor_char_boundary(bytes, max_bytes);
+            self.truncate_bytes(end);
+        }
+    }
+
+    /// Shortens the string to at most `max_bytes` bytes, excluding the
+    /// terminator, and ends it with `ellipsis` if anything was cut.
+    ///
+    /// This fits long names and paths into fixed width C UI fields. Pass
+    /// `"…"` or `"..."` depending on what the field can display. If
+    /// `ellipsis` is longer than `max_bytes` the string is cut without it.
+    pub fn truncate_with_ellipsis(&mut self, max_bytes: usize, ellipsis: &str) {
+        let bytes = self.to_bytes();
+
+        if bytes.len() <= max_bytes {
+            return;
+        }
+
+        if ellipsis.len() > max_bytes {
+            self.truncate_to_char_boundary(max_bytes);
+            return;
+        }
+
+        let end = floor_char_boundary(bytes, max_bytes - ellipsis.len());
+        self.truncate_bytes(end);
+
+        // Cutting freed at least as much as the ellipsis takes, so this
+        // stays within the old length and never needs a new allocation
+        // from the budget.
+        self.push_bytes(ellipsis.as_bytes()).unwrap();
+    }
+
+    /// Returns an adapter that displays the buffer as an offset/hex/ASCII
+    /// dump, including the terminator.
+    ///
+    /// In debug builds the unused capacity of the inline buffer is shown as
+    /// well, which helps when checking what a C function wrote into it.
+    /// Bytes nothing wrote to show up as `aa`, and the guard bytes checked
+    /// by `verify` as `fd` right after the terminator.
+    pub fn hex_dump(&self) -> HexDump<'_> {
+        let bytes = match *self {
+            // Poisoned in `new`, so the whole buffer is initialized
+            #[cfg(debug_assertions)]
+            CFixedStringN::Local { ref s, .. } => unsafe {
+                std::slice::from_raw_parts(s.as_ptr() as *const u8, N)
+            },
+            _ => self.to_bytes_with_nul(),
+        };
+
+        HexDump::new(bytes)
+    }
+
+    /// Returns the contents as an `OsStr` without allocating, so it can be
+    /// compared against values from `std::env` and `std::fs`.
+    pub fn as_os_str(&self) -> &OsStr {
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            OsStr::from_bytes(self.to_bytes())
+        }
+
+        #[cfg(not(unix))]
+        {
+            OsStr::new(unsafe { self.as_str() })
+        }
+    }
+}
+
+impl<const N: usize> Drop for CFixedStringN<N> {
+    fn drop(&mut self) {
+        // Panicking again while unwinding would abort
+        if !std::thread::panicking() {
+            self.verify();
+        }
+
+        if let CFixedStringN::Heap { ref s, .. } = *self {
+            HEAP_BUDGET.release(s.capacity());
+        }
+    }
+}
+
+impl<const N: usize> Clone for CFixedStringN<N> {
+    fn clone(&self) -> Self {
+        match *self {
+            CFixedStringN::Local { ref s, len } => {
+                let mut t = Self::new();
+
+                // Only the string and its terminator are initialized
+                if let CFixedStringN::Local {
+                    s: ref mut dst,
+                    len: ref mut dst_len,
+                } = t
+                {
+                    dst[..=len].copy_from_slice(&s[..=len]);
+                    *dst_len = len;
+                }
+
+                t.write_canary();
+                t
             }
+            // Panics like `From<&str>` if the budget rejects the copy
+            CFixedStringN::Heap { .. } => Self::copied(self.to_bytes()).unwrap(),
+            CFixedStringN::Static { s } => CFixedStringN::Static { s },
         }
     }
 }
 
-impl<'a> From<&'a str> for CFixedString {
-    fn from(s: &'a str) -> Self {
-        use std::fmt::Write;
+impl<const N: usize> Default for CFixedStringN<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Debug for CFixedStringN<N> {
+    /// Prints the escaped contents and where they are stored, such as
+    /// `CFixedString("text", inline)`, or `CFixedStringN<64>("text", inline)`
+    /// for sizes other than the default one
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let storage = match *self {
+            CFixedStringN::Local { .. } => "inline",
+            CFixedStringN::Heap { .. } => "heap",
+            CFixedStringN::Static { .. } => "static",
+        };
+
+        let sized;
+        let name = if N == STRING_SIZE {
+            "CFixedString"
+        } else {
+            sized = format!("CFixedStringN<{}>", N);
+            &sized
+        };
+
+        f.debug_tuple(name)
+            .field(&&*self.to_string())
+            .field(&format_args!("{}", storage))
+            .finish()
+    }
+}
+
+impl<const N: usize> fmt::Display for CFixedStringN<N> {
+    /// Writes the contents like `to_string` does, with invalid UTF-8
+    /// replaced by `U+FFFD REPLACEMENT CHARACTER`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.to_string(), f)
+    }
+}
+
+impl<'a, const N: usize> From<&'a str> for CFixedStringN<N> {
+    fn from(s: &'a str) -> Self {
+        // Straight to `push_bytes` rather than through `fmt::Write`, which is
+        // a single copy into the inline buffer for short strings.
+        let mut string = Self::new();
+        string.push_bytes(s.as_bytes()).unwrap();
+        string
+    }
+}
+
+impl<const N: usize> std::str::FromStr for CFixedStringN<N> {
+    type Err = Error;
+
+    /// Same as `try_from_str`, zero bytes are rejected whatever the
+    /// `NulPolicy` is
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Self::try_from_str(s)
+    }
+}
+
+impl<const N: usize> From<&'static CStr> for CFixedStringN<N> {
+    /// Borrows the constant string without a copy, such as a `c"..."`
+    /// literal. A string that isn't valid UTF-8 is copied instead, with
+    /// the invalid sequences replaced like `to_string` does.
+    fn from(s: &'static CStr) -> Self {
+        match s.to_str() {
+            Ok(_) => CFixedStringN::Static { s },
+            Err(_) => Self::from(&*s.to_string_lossy()),
+        }
+    }
+}
+
+impl<const N: usize> fmt::Write for CFixedStringN<N> {
+    fn write_str(&mut self, s: &str) -> Result<(), fmt::Error> {
+        self.push_bytes(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+
+    fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> Result<(), fmt::Error> {
+        self.try_write_fmt(args).map_err(|_| fmt::Error)
+    }
+}
+
+impl<'a, const N: usize, const M: usize> Extend<&'a CFixedStringN<M>> for CFixedStringN<N> {
+    fn extend<I: IntoIterator<Item = &'a CFixedStringN<M>>>(&mut self, iter: I) {
+        for other in iter {
+            self.push_cfixed(other).unwrap();
+        }
+    }
+}
+
+// Like `From<&str>`, these and the `Add` impls below panic if
+// `NulPolicy::Reject` rejects a zero byte or the heap budget rejects the
+// growth.
+impl<const N: usize> Extend<char> for CFixedStringN<N> {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        let mut buf = [0; 4];
+
+        for c in iter {
+            self.push_bytes(c.encode_utf8(&mut buf).as_bytes()).unwrap();
+        }
+    }
+}
+
+impl<'a, const N: usize> Extend<&'a str> for CFixedStringN<N> {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        for s in iter {
+            self.push_bytes(s.as_bytes()).unwrap();
+        }
+    }
+}
+
+impl<const N: usize> FromIterator<char> for CFixedStringN<N> {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        let mut t = Self::new();
+        t.extend(iter);
+        t
+    }
+}
+
+impl<'a, const N: usize> FromIterator<&'a str> for CFixedStringN<N> {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        let mut t = Self::new();
+        t.extend(iter);
+        t
+    }
+}
+
+impl<'a, const N: usize> ops::Add<&'a str> for CFixedStringN<N> {
+    type Output = Self;
+
+    fn add(mut self, s: &'a str) -> Self {
+        self += s;
+        self
+    }
+}
+
+impl<'a, const N: usize, const M: usize> ops::Add<&'a CFixedStringN<M>> for CFixedStringN<N> {
+    type Output = Self;
+
+    fn add(mut self, other: &'a CFixedStringN<M>) -> Self {
+        self += other;
+        self
+    }
+}
+
+impl<'a, const N: usize> ops::AddAssign<&'a str> for CFixedStringN<N> {
+    fn add_assign(&mut self, s: &'a str) {
+        self.push_bytes(s.as_bytes()).unwrap();
+    }
+}
+
+impl<'a, const N: usize, const M: usize> ops::AddAssign<&'a CFixedStringN<M>> for CFixedStringN<N> {
+    fn add_assign(&mut self, other: &'a CFixedStringN<M>) {
+        self.push_cfixed(other).unwrap();
+    }
+}
+
+impl<const N: usize> From<CFixedStringN<N>> for String {
+    fn from(s: CFixedStringN<N>) -> Self {
+        String::from_utf8_lossy(s.to_bytes()).into_owned()
+    }
+}
+
+impl<const N: usize> From<CFixedStringN<N>> for CString {
+    /// Converts into a `CString`, which only copies if the string is not
+    /// already heap allocated
+    fn from(mut s: CFixedStringN<N>) -> Self {
+        match s {
+            // Writes never leave zero bytes in the string, see `NulPolicy`
+            CFixedStringN::Heap { .. } => unsafe {
+                CString::from_vec_with_nul_unchecked(s.take_heap())
+            },
+            ref local => CString::from(&**local),
+        }
+    }
+}
+
+impl<'a, const N: usize> From<&'a CFixedStringN<N>> for Cow<'a, CStr> {
+    fn from(s: &'a CFixedStringN<N>) -> Self {
+        s.as_cow_cstr()
+    }
+}
+
+impl<const N: usize> ops::Deref for CFixedStringN<N> {
+    type Target = CStr;
+
+    fn deref(&self) -> &CStr {
+        use std::slice;
+
+        match *self {
+            CFixedStringN::Local { ref s, len } => unsafe {
+                to_cstr(slice::from_raw_parts(s.as_ptr() as *const u8, len + 1))
+            },
+            CFixedStringN::Heap { ref s, .. } => unsafe { to_cstr(s) },
+            CFixedStringN::Static { s } => s,
+        }
+    }
+}
+
+/// Slices the contents like `String` does, panicking if a range end is
+/// out of bounds or not on a character boundary
+impl<const N: usize, I: std::slice::SliceIndex<str>> ops::Index<I> for CFixedStringN<N> {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &I::Output {
+        &(unsafe { self.as_str() })[index]
+    }
+}
+
+// There is no `Borrow<CStr>` to go with this any more, `CStr` hashes
+// differently from the `str` the string hashes like. Maps are searched with
+// a C string through `CFixedStr::from_cstr` instead, see CHANGELOG.md for
+// the breaking change.
+impl<const N: usize> AsRef<CStr> for CFixedStringN<N> {
+    fn as_ref(&self) -> &CStr {
+        self
+    }
+}
+
+impl<const N: usize> Borrow<str> for CFixedStringN<N> {
+    fn borrow(&self) -> &str {
+        unsafe { self.as_str() }
+    }
+}
+
+impl<const N: usize> AsRef<str> for CFixedStringN<N> {
+    fn as_ref(&self) -> &str {
+        unsafe { self.as_str() }
+    }
+}
+
+// There is no `Borrow<[u8]>` to go with this, byte slices hash differently
+// from the `str` the string hashes like, so map lookups with them would miss.
+impl<const N: usize> AsRef<[u8]> for CFixedStringN<N> {
+    /// Returns the contents without the terminator
+    fn as_ref(&self) -> &[u8] {
+        self.to_bytes()
+    }
+}
+
+impl<const N: usize> AsRef<OsStr> for CFixedStringN<N> {
+    fn as_ref(&self) -> &OsStr {
+        self.as_os_str()
+    }
+}
+
+impl<const N: usize> PartialEq<OsStr> for CFixedStringN<N> {
+    fn eq(&self, other: &OsStr) -> bool {
+        self.as_os_str() == other
+    }
+}
+
+impl<'a, const N: usize> PartialEq<&'a OsStr> for CFixedStringN<N> {
+    fn eq(&self, other: &&'a OsStr) -> bool {
+        self.as_os_str() == *other
+    }
+}
+
+impl<const N: usize> PartialEq<OsString> for CFixedStringN<N> {
+    fn eq(&self, other: &OsString) -> bool {
+        self.as_os_str() == other.as_os_str()
+    }
+}
+
+impl<const N: usize> PartialEq<CFixedStringN<N>> for OsStr {
+    fn eq(&self, other: &CFixedStringN<N>) -> bool {
+        self == other.as_os_str()
+    }
+}
+
+impl<const N: usize> PartialEq<CFixedStringN<N>> for &OsStr {
+    fn eq(&self, other: &CFixedStringN<N>) -> bool {
+        *self == other.as_os_str()
+    }
+}
+
+impl<const N: usize> PartialEq<CFixedStringN<N>> for OsString {
+    fn eq(&self, other: &CFixedStringN<N>) -> bool {
+        self.as_os_str() == other.as_os_str()
+    }
+}
+
+impl<const N: usize> PartialEq<str> for CFixedStringN<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.to_bytes() == other.as_bytes()
+    }
+}
+
+impl<const N: usize> PartialEq<CFixedStringN<N>> for str {
+    fn eq(&self, other: &CFixedStringN<N>) -> bool {
+        self.as_bytes() == other.to_bytes()
+    }
+}
+
+impl<'a, const N: usize> PartialEq<&'a str> for CFixedStringN<N> {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.to_bytes() == other.as_bytes()
+    }
+}
 
-        let mut string = CFixedString::new();
-        string.write_str(s).unwrap();
-        string
+impl<const N: usize> PartialEq<CFixedStringN<N>> for &str {
+    fn eq(&self, other: &CFixedStringN<N>) -> bool {
+        self.as_bytes() == other.to_bytes()
     }
 }
 
-impl fmt::Write for CFixedString {
-    fn write_str(&mut self, s: &str) -> Result<(), fmt::Error> {
-        unsafe {
-            let cur_len = self.as_str().len();
-
-            match cur_len + s.len() {
-                len if len < STRING_SIZE => match *self {
-                    CFixedString::Local {
-                        s: ref mut ls,
-                        len: ref mut lslen,
-                    } => {
-                        let ptr = ls.as_mut_ptr() as *mut u8;
-                        ptr::copy(s.as_ptr(), ptr.add(cur_len), s.len());
-                        *ptr.add(len) = 0;
-                        *lslen = len;
-                    }
-                    _ => unreachable!(),
-                },
-                len => {
-                    let mut heapstring = String::with_capacity(len + 1);
-
-                    heapstring.write_str(self.as_str())?;
-                    heapstring.write_str(s)?;
-
-                    *self = CFixedString::Heap {
-                        s: CString::new(heapstring).unwrap(),
-                        len,
-                    };
-                }
-            }
-        }
+impl<const N: usize> PartialEq<String> for CFixedStringN<N> {
+    fn eq(&self, other: &String) -> bool {
+        self.to_bytes() == other.as_bytes()
+    }
+}
 
-        Ok(())
+impl<const N: usize> PartialEq<CFixedStringN<N>> for String {
+    fn eq(&self, other: &CFixedStringN<N>) -> bool {
+        self.as_bytes() == other.to_bytes()
     }
 }
 
-impl From<CFixedString> for String {
-    fn from(s: CFixedString) -> Self {
-        String::from_utf8_lossy(s.to_bytes()).into_owned()
+impl<const N: usize> PartialEq<CStr> for CFixedStringN<N> {
+    fn eq(&self, other: &CStr) -> bool {
+        **self == *other
     }
 }
 
-impl ops::Deref for CFixedString {
-    type Target = CStr;
+impl<const N: usize> PartialEq<CFixedStringN<N>> for CStr {
+    fn eq(&self, other: &CFixedStringN<N>) -> bool {
+        *self == **other
+    }
+}
 
-    fn deref(&self) -> &CStr {
-        use std::slice;
+impl<'a, const N: usize> PartialEq<&'a CStr> for CFixedStringN<N> {
+    fn eq(&self, other: &&'a CStr) -> bool {
+        **self == **other
+    }
+}
 
-        match *self {
-            CFixedString::Local { ref s, len } => unsafe {
-                mem::transmute(slice::from_raw_parts(s.as_ptr(), len + 1))
-            },
-            CFixedString::Heap { ref s, .. } => s,
-        }
+impl<const N: usize> PartialEq<CFixedStringN<N>> for &CStr {
+    fn eq(&self, other: &CFixedStringN<N>) -> bool {
+        **self == **other
     }
 }
 
-impl Borrow<CStr> for CFixedString {
-    fn borrow(&self) -> &CStr {
-        self
+impl<const N: usize> PartialEq<CString> for CFixedStringN<N> {
+    fn eq(&self, other: &CString) -> bool {
+        **self == **other
     }
 }
 
-impl AsRef<CStr> for CFixedString {
-    fn as_ref(&self) -> &CStr {
-        self
+impl<const N: usize> PartialEq<CFixedStringN<N>> for CString {
+    fn eq(&self, other: &CFixedStringN<N>) -> bool {
+        **self == **other
     }
 }
 
-impl Borrow<str> for CFixedString {
-    fn borrow(&self) -> &str {
-        unsafe { self.as_str() }
+/// Views the string and terminator in `bytes` as a `CStr`.
+///
+/// # Safety
+///
+/// `bytes` must end in its only zero byte, which debug builds check.
+unsafe fn to_cstr(bytes: &[u8]) -> &CStr {
+    debug_assert!(
+        CStr::from_bytes_with_nul(bytes).is_ok(),
+        "C string without a terminator or with an interior zero byte"
+    );
+
+    CStr::from_bytes_with_nul_unchecked(bytes)
+}
+
+/// Fails with the position of the first zero byte in `bytes`, if any
+fn check_no_nul(bytes: &[u8]) -> Result<(), Error> {
+    match bytes.iter().position(|&b| b == 0) {
+        Some(position) => Err(Error::InteriorNul { position }),
+        None => Ok(()),
     }
 }
 
-impl AsRef<str> for CFixedString {
-    fn as_ref(&self) -> &str {
-        unsafe { self.as_str() }
+/// Returns the largest index at or below `index` that doesn't split a
+/// UTF-8 sequence in `bytes`
+fn floor_char_boundary(bytes: &[u8], index: usize) -> usize {
+    if index >= bytes.len() {
+        return bytes.len();
+    }
+
+    // Only moves back to a start byte whose sequence reaches `index`, so
+    // stray continuation bytes don't pull the characters before them along
+    for start in (index.saturating_sub(3)..=index).rev() {
+        if bytes[start] & 0xc0 != 0x80 {
+            return if start + utf8_width(bytes[start]) > index {
+                start
+            } else {
+                index
+            };
+        }
+    }
+
+    index
+}
+
+// The length of the UTF-8 sequence that `lead` starts, 1 for ASCII and for
+// bytes that can't start one
+fn utf8_width(lead: u8) -> usize {
+    match lead {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => 1,
     }
 }
 
@@ -187,6 +1268,44 @@ macro_rules! format_c {
     })
 }
 
+/// Like `format_c!`, but returns a `Result` instead of panicking when a
+/// zero byte or the heap budget stops the write, for formatting untrusted
+/// data inside `extern "C"` functions.
+///
+/// ```
+/// use cfixed_string::{try_format_c, Error};
+///
+/// let name = "bad\0name";
+/// assert!(try_format_c!("user {}", 42).is_ok());
+/// assert_eq!(try_format_c!("user {}", name).err(), Some(Error::InteriorNul { position: 3 }));
+/// ```
+#[macro_export]
+macro_rules! try_format_c {
+    ($($arg:tt)+) => ({
+        let mut fixed = $crate::CFixedString::new();
+        fixed.try_write_fmt(format_args!($($arg)+)).map(|()| fixed)
+    })
+}
+
+/// Formats a message into a thread-local buffer and passes it to a C
+/// logging callback of the form `extern "C" fn(level, *const c_char)`,
+/// without allocating for messages that fit the inline buffer.
+///
+/// Logging can't fail, so a message that can't be written whole is passed
+/// on cut where the write failed. With the default `NulPolicy::Reject` an
+/// argument containing a zero byte is left out with everything after it,
+/// and the same goes for the part that runs out of heap budget.
+///
+/// ```ignore
+/// log_c!(engine_log, LOG_WARN, "missing texture {}", name);
+/// ```
+#[macro_export]
+macro_rules! log_c {
+    ($logger:expr, $level:expr, $($arg:tt)+) => {
+        $crate::__log_c($logger, $level, format_args!($($arg)+))
+    };
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -200,7 +1319,7 @@ mod tests {
         }
 
         for i in 0..len % 16 {
-            out.write_char((i as u8 + 'A' as u8) as char).unwrap();
+            out.write_char((i as u8 + b'A') as char).unwrap();
         }
 
         assert_eq!(out.len(), len);
@@ -239,8 +1358,8 @@ mod tests {
 
     #[test]
     fn test_511() {
-        // this string (width 511) buffer should just fit
-        let test_511_string = gen_string(511);
+        // this string (width 511 by default) buffer should just fit
+        let test_511_string = gen_string(STRING_SIZE - 1);
 
         let t = CFixedString::from_str(&test_511_string);
 
@@ -250,8 +1369,8 @@ mod tests {
 
     #[test]
     fn test_512() {
-        // this string (width 512) buffer should not fit
-        let test_512_string = gen_string(512);
+        // this string (width 512 by default) buffer should not fit
+        let test_512_string = gen_string(STRING_SIZE);
 
         let t = CFixedString::from_str(&test_512_string);
 
@@ -261,8 +1380,8 @@ mod tests {
 
     #[test]
     fn test_513() {
-        // this string (width 513) buffer should not fit
-        let test_513_string = gen_string(513);
+        // this string (width 513 by default) buffer should not fit
+        let test_513_string = gen_string(STRING_SIZE + 1);
 
         let t = CFixedString::from_str(&test_513_string);
 
@@ -279,7 +1398,7 @@ mod tests {
         assert!(!t.is_allocated());
         assert_eq!(&String::from(t), short);
 
-        let long = gen_string(1025);
+        let long = gen_string(STRING_SIZE * 2 + 1);
 
         let t = CFixedString::from_str(&long);
 
@@ -288,6 +1407,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::write_literal)]
     fn test_short_format() {
         let mut fixed = CFixedString::new();
 
@@ -327,8 +1447,8 @@ mod tests {
     fn test_short_fmt_macro() {
         let first = 23;
         let second = "#@!*()&^%_-+={}[]|\\/?><,.:;~`";
-        let third = u32::max_value();
-        let fourth = gen_string(512 - 45);
+        let third = u32::MAX;
+        let fourth = gen_string(STRING_SIZE - 45);
 
         let fixed = format_c!("{}_{}_0x{:x}_{}", first, second, third, fourth);
         let heaped = format!("{}_{}_0x{:x}_{}", first, second, third, fourth);
@@ -340,9 +1460,9 @@ mod tests {
     #[test]
     fn test_long_fmt_macro() {
         let first = "";
-        let second = gen_string(510);
+        let second = gen_string(STRING_SIZE - 2);
         let third = 3;
-        let fourth = gen_string(513 * 8);
+        let fourth = gen_string((STRING_SIZE + 1) * 8);
 
         let fixed = format_c!("{}_{}{}{}", first, second, third, fourth);
         let heaped = format!("{}_{}{}{}", first, second, third, fourth);
@@ -350,4 +1470,621 @@ mod tests {
         assert!(fixed.is_allocated());
         assert_eq!(&fixed.to_string(), &heaped);
     }
+
+    #[test]
+    fn test_os_str() {
+        let t = CFixedString::from_str("some/path.txt");
+        let os = OsString::from("some/path.txt");
+
+        assert_eq!(t.as_os_str(), os.as_os_str());
+        assert!(t == os);
+        assert!(os == t);
+        assert!(t == *OsStr::new("some/path.txt"));
+        assert!(t != OsStr::new("some/other.txt"));
+    }
+
+    #[test]
+    fn test_into_cstring() {
+        let short = CFixedString::from_str("short");
+        assert_eq!(CString::from(short).as_bytes(), b"short");
+
+        let long = gen_string(STRING_SIZE * 2);
+        let heap = CFixedString::from_str(&long);
+        let ptr = heap.as_ptr();
+        let cstring = CString::from(heap);
+
+        // the heap allocation is handed over as is
+        assert_eq!(cstring.as_ptr(), ptr);
+        assert_eq!(cstring.as_bytes(), long.as_bytes());
+
+        let t = CFixedString::from_str("cow");
+        let cow = Cow::from(&t);
+        assert_eq!(cow.to_bytes(), b"cow");
+        assert!(matches!(t.as_cow_cstr(), Cow::Borrowed(c) if c.as_ptr() == t.as_ptr()));
+    }
+
+    #[test]
+    fn test_into_string() {
+        let t = CFixedString::from_str("valid");
+        assert_eq!(t.into_string().unwrap(), "valid");
+
+        let long = gen_string(STRING_SIZE + 1);
+        let t = CFixedString::from_str(&long);
+        assert_eq!(t.into_string().unwrap(), long);
+
+        let mut t = CFixedString::new();
+        if let CFixedString::Local {
+            ref mut s,
+            ref mut len,
+        } = t
+        {
+            s[0] = mem::MaybeUninit::new(b'a' as c_char);
+            s[1] = mem::MaybeUninit::new(0xff_u8 as c_char);
+            s[2] = mem::MaybeUninit::new(0);
+            *len = 2;
+        }
+        t.write_canary();
+
+        let err = t.into_string().unwrap_err();
+        assert_eq!(err.utf8_error().valid_up_to(), 1);
+        assert_eq!(err.into_cfixed_string().to_bytes(), b"a\xff");
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        let t = CFixedString::from_str("dump");
+        let dump = t.hex_dump().to_string();
+
+        assert!(dump.starts_with("00000000  64 75 6d 70 00 "));
+
+        let long = gen_string(STRING_SIZE);
+        let t = CFixedString::from_str(&long);
+        let last_line = format!("{:08x} ", STRING_SIZE);
+
+        assert!(t
+            .hex_dump()
+            .to_string()
+            .lines()
+            .last()
+            .unwrap()
+            .starts_with(&last_line));
+    }
+
+    #[test]
+    fn test_xml_escaped() {
+        let mut t = CFixedString::from_str("<a title=");
+        t.push_xml_escaped("\"Tom & Jerry's\" <show>").unwrap();
+
+        assert_eq!(
+            &t.to_string(),
+            "<a title=&quot;Tom &amp; Jerry&#39;s&quot; &lt;show&gt;"
+        );
+    }
+
+    #[test]
+    fn test_write_display() {
+        let mut t = CFixedString::new();
+
+        t.write_display(&"value=").unwrap();
+        t.write_display(&42).unwrap();
+        t.write_display(&'!').unwrap();
+
+        assert_eq!(&t.to_string(), "value=42!");
+
+        struct Failing;
+
+        impl fmt::Display for Failing {
+            fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+                Err(fmt::Error)
+            }
+        }
+
+        assert_eq!(t.write_display(&Failing), Err(Error::Format));
+    }
+
+    #[test]
+    fn test_push_bytes_utf8_partial() {
+        let mut t = CFixedString::new();
+        let input = "größe: 3€".as_bytes();
+
+        // split in the middle of 'ö' and of '€'
+        let (first, rest) = input.split_at(3);
+        assert_eq!(t.push_bytes_utf8_partial(first), 2);
+
+        let mut pending = first[2..].to_vec();
+        pending.extend_from_slice(&rest[..rest.len() - 1]);
+        assert_eq!(t.push_bytes_utf8_partial(&pending), pending.len() - 2);
+
+        let mut pending = pending[pending.len() - 2..].to_vec();
+        pending.push(*rest.last().unwrap());
+        assert_eq!(t.push_bytes_utf8_partial(&pending), 3);
+
+        assert_eq!(&t.to_string(), "größe: 3€");
+
+        t.push_bytes_utf8_partial(b"!\xffok");
+        assert_eq!(&t.to_string(), "größe: 3€!\u{fffd}ok");
+    }
+
+    #[test]
+    fn test_push_cfixed() {
+        let mut t = CFixedString::from_str("head");
+        let long = CFixedString::from_str(gen_string(STRING_SIZE));
+
+        t.push_cfixed(&CFixedString::from_str("-tail")).unwrap();
+        assert_eq!(&t.to_string(), "head-tail");
+
+        t.extend(&[CFixedString::from_str("-"), long]);
+        assert!(t.is_allocated());
+        assert_eq!(t.to_bytes().len(), "head-tail-".len() + STRING_SIZE);
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("2"), 2);
+        assert_eq!(parse_size("4096"), 4096);
+    }
+
+    #[test]
+    fn test_write_joined() {
+        let mut t = CFixedString::from_str("names: ");
+
+        t.write_joined(&["vertex", "normal", "uv"], ", ").unwrap();
+        t.write_joined(std::iter::empty::<u32>(), ", ").unwrap();
+
+        assert_eq!(&t.to_string(), "names: vertex, normal, uv");
+
+        let mut t = CFixedString::new();
+        t.write_joined(1..=3, "-").unwrap();
+        assert_eq!(&t.to_string(), "1-2-3");
+
+        let t = CFixedString::join(&["/usr/lib", "/opt/lib"], ":").unwrap();
+        assert_eq!(t, "/usr/lib:/opt/lib");
+        assert_eq!(
+            CFixedString::join(&["a", "b\0"], ",").err(),
+            Some(Error::InteriorNul { position: 1 })
+        );
+    }
+
+    #[test]
+    fn test_truncate_to_char_boundary() {
+        let mut t = CFixedString::from_str("naïve");
+
+        t.truncate_to_char_boundary(10);
+        assert_eq!(&t.to_string(), "naïve");
+
+        // 'ï' takes bytes 2 and 3
+        t.truncate_to_char_boundary(3);
+        assert_eq!(t.to_bytes_with_nul(), b"na\0");
+
+        let long = "é".repeat(STRING_SIZE);
+        let mut t = CFixedString::from_str(&long);
+
+        t.truncate_to_char_boundary(STRING_SIZE * 2 - 1);
+        assert!(t.is_allocated());
+        assert_eq!(&t.to_string(), &long[..STRING_SIZE * 2 - 2]);
+
+        t.truncate_to_char_boundary(4);
+        t.write_str("-x").unwrap();
+        assert!(t.is_allocated());
+        assert_eq!(&t.to_string(), "éé-x");
+    }
+
+    #[test]
+    fn test_truncate_with_ellipsis() {
+        let mut t = CFixedString::from_str("short");
+        t.truncate_with_ellipsis(5, "…");
+        assert_eq!(&t.to_string(), "short");
+
+        // "…" is three bytes
+        let mut t = CFixedString::from_str("/home/user/projects");
+        t.truncate_with_ellipsis(10, "…");
+        assert_eq!(&t.to_string(), "/home/u…");
+
+        let mut t = CFixedString::from_str("ääää");
+        t.truncate_with_ellipsis(6, "...");
+        assert_eq!(&t.to_string(), "ä...");
+
+        let mut t = CFixedString::from_str("abcdef");
+        t.truncate_with_ellipsis(2, "...");
+        assert_eq!(&t.to_string(), "ab");
+
+        let mut t = CFixedString::from_str(gen_string(STRING_SIZE * 2));
+        t.truncate_with_ellipsis(STRING_SIZE + 10, "...");
+        assert_eq!(t.to_bytes().len(), STRING_SIZE + 10);
+        assert!(t.to_bytes().ends_with(b"..."));
+    }
+
+    #[test]
+    fn test_custom_size() {
+        let t = CFixedStringN::<16>::from_str(gen_string(15));
+        assert!(!t.is_allocated());
+
+        let mut t = CFixedStringN::<16>::from_str(gen_string(15));
+        t.write_str("x").unwrap();
+        assert!(t.is_allocated());
+        assert_eq!(t.to_bytes().len(), 16);
+
+        // Strings of different sizes can be mixed
+        let mut big = CFixedStringN::<1024>::new();
+        big.push_cfixed(&t).unwrap();
+        big.push_cfixed(&CFixedString::from_str("-default"))
+            .unwrap();
+        assert!(!big.is_allocated());
+        assert_eq!(big.to_bytes().len(), 16 + 8);
+    }
+
+    #[test]
+    fn test_capacity() {
+        assert_eq!(CFixedString::INLINE_CAPACITY, STRING_SIZE - 1);
+        assert_eq!(CFixedStringN::<64>::INLINE_CAPACITY, 63);
+
+        let mut t = CFixedStringN::<16>::from_str("hello");
+        assert_eq!(t.capacity(), 15);
+        assert_eq!(t.remaining_capacity(), 10);
+
+        t.write_str("0123456789").unwrap();
+        assert!(!t.is_allocated());
+        assert_eq!(t.remaining_capacity(), 0);
+
+        // The buffer doubles on the first spill, later appends reuse it
+        t.write_str("!").unwrap();
+        assert!(t.is_allocated());
+        assert_eq!(t.capacity(), 31);
+        assert_eq!(t.remaining_capacity(), 15);
+
+        let ptr = t.as_ptr();
+        t.write_str("0123456789").unwrap();
+        assert_eq!(t.as_ptr(), ptr);
+        assert_eq!(t.to_bytes_with_nul(), b"hello0123456789!0123456789\0");
+
+        assert!(t.write_str("\0").is_err());
+        assert_eq!(t.to_bytes().len(), 26);
+    }
+
+    #[test]
+    fn test_from_fmt() {
+        let t = CFixedString::from_fmt(format_args!("{}-{}", "id", 7)).unwrap();
+        assert!(!t.is_allocated());
+        assert_eq!(t.to_bytes(), b"id-7");
+
+        // Sized exactly, without first filling the inline buffer
+        let long = "f".repeat(STRING_SIZE);
+        let t = CFixedString::from_fmt(format_args!("{}{}", long, 42)).unwrap();
+        assert!(t.is_allocated());
+        assert_eq!(t.capacity(), long.len() + 2);
+        assert!(t.to_bytes().ends_with(b"42"));
+    }
+
+    #[test]
+    fn test_reserve() {
+        let mut t = CFixedStringN::<8>::from_str("abc");
+        t.reserve(4).unwrap();
+        assert!(!t.is_allocated());
+
+        t.reserve(20).unwrap();
+        assert!(t.is_allocated());
+        assert_eq!(t.capacity(), 23);
+
+        let ptr = t.as_ptr();
+        t.write_str(&"r".repeat(20)).unwrap();
+        assert_eq!(t.as_ptr(), ptr);
+        assert_eq!(t.remaining_capacity(), 0);
+    }
+
+    #[test]
+    fn test_shrink_to_fit() {
+        let mut t = CFixedStringN::<8>::from_str("a long heap string");
+        t.truncate(5);
+        assert!(t.is_allocated());
+
+        t.shrink_to_fit();
+        assert!(!t.is_allocated());
+        assert_eq!(t, "a lon");
+        assert_eq!(t.capacity(), 7);
+        t.push_str("g");
+        assert_eq!(t, "a long");
+
+        let mut t = CFixedStringN::<8>::with_capacity(600).unwrap();
+        t.push_str("heap for good");
+        t.shrink_to_fit();
+        assert!(t.is_allocated());
+        assert_eq!(t, "heap for good");
+        assert!(t.capacity() < 600);
+
+        let mut t = CFixedString::from_static("static\0");
+        t.shrink_to_fit();
+        assert!(t.is_static());
+    }
+
+    #[test]
+    fn test_with_capacity() {
+        let t = CFixedStringN::<8>::with_capacity(7).unwrap();
+        assert!(!t.is_allocated());
+        assert_eq!(t, "");
+
+        let mut t = CFixedStringN::<8>::with_capacity(600).unwrap();
+        assert!(t.is_allocated());
+        assert_eq!(t.capacity(), 600);
+
+        let ptr = t.as_ptr();
+        t.write_str(&"w".repeat(600)).unwrap();
+        assert_eq!(t.as_ptr(), ptr);
+        assert_eq!(t.to_bytes().len(), 600);
+    }
+
+    #[test]
+    fn test_literal_writes() {
+        let mut t = CFixedString::new();
+        write!(&mut t, "literal").unwrap();
+        write!(&mut t, " {{escaped}}").unwrap();
+        t.write_display(&format_args!("!")).unwrap();
+        assert_eq!(t.to_bytes(), b"literal {escaped}!");
+
+        let t = CFixedString::from_fmt(format_args!("constant")).unwrap();
+        assert_eq!(t.capacity(), CFixedString::INLINE_CAPACITY);
+    }
+
+    #[test]
+    fn test_static() {
+        static NAME: &str = "u_color\0";
+
+        let mut t = CFixedString::from_static(NAME);
+        assert!(t.is_static());
+        assert_eq!(t.as_ptr(), NAME.as_ptr() as *const c_char);
+        assert_eq!(t.to_bytes(), b"u_color");
+
+        // The first change copies the constant
+        t.write_str("[0]").unwrap();
+        assert!(!t.is_static());
+        assert_eq!(t.to_bytes(), b"u_color[0]");
+
+        let t = CFixedString::from_static("no terminator");
+        assert!(!t.is_static());
+        assert_eq!(t.to_bytes(), b"no terminator");
+
+        let c = CStr::from_bytes_with_nul(b"constant\0").unwrap();
+        let mut t = CFixedString::from(c);
+        assert!(t.is_static());
+        assert_eq!(unsafe { t.as_str() }, "constant");
+        t.truncate_to_char_boundary(5);
+        assert_eq!(t.to_bytes_with_nul(), b"const\0");
+        assert_eq!(c.to_bytes(), b"constant");
+
+        let c = CStr::from_bytes_with_nul(b"bad \xff\0").unwrap();
+        let t = CFixedString::from(c);
+        assert!(!t.is_static());
+        assert_eq!(t, "bad \u{fffd}");
+    }
+
+    #[test]
+    fn test_into_heap() {
+        let t = CFixedString::from_str("moved into a struct").into_heap();
+        assert!(t.is_allocated());
+        assert_eq!(t.capacity(), 19);
+
+        let ptr = t.as_ptr();
+        let boxed = Box::new(t);
+        assert_eq!(boxed.as_ptr(), ptr);
+        assert_eq!(boxed.to_bytes(), b"moved into a struct");
+
+        let mut t = CFixedString::from_static("static\0");
+        t.make_heap().unwrap();
+        assert!(t.is_allocated());
+        assert_eq!(t.to_bytes_with_nul(), b"static\0");
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "interior zero byte")]
+    fn test_interior_nul_checked_on_deref() {
+        let mut t = CFixedString::new();
+
+        if let CFixedString::Local {
+            ref mut s,
+            ref mut len,
+        } = t
+        {
+            s[..4].copy_from_slice(&[
+                mem::MaybeUninit::new(b'a' as c_char),
+                mem::MaybeUninit::new(0),
+                mem::MaybeUninit::new(b'b' as c_char),
+                mem::MaybeUninit::new(0),
+            ]);
+            *len = 3;
+        }
+
+        let _ = t.to_bytes();
+    }
+
+    #[test]
+    fn test_try_from_str() {
+        let t = CFixedString::try_from_str("plain").unwrap();
+        assert_eq!(t.to_bytes(), b"plain");
+
+        let long = "t".repeat(STRING_SIZE);
+        assert!(CFixedString::try_from_str(&long).unwrap().is_allocated());
+
+        let err = CFixedString::try_from_str("bad\0input").err();
+        assert_eq!(err, Some(Error::InteriorNul { position: 3 }));
+    }
+
+    #[test]
+    fn test_try_format_c() {
+        let long = gen_string(STRING_SIZE * 2);
+
+        let t = try_format_c!("{}-{}", 1, 2).unwrap();
+        assert_eq!(t.to_bytes(), b"1-2");
+
+        let t = try_format_c!("{}", long).unwrap();
+        assert!(t.is_allocated());
+        assert_eq!(t.to_bytes(), long.as_bytes());
+
+        let t = try_format_c!("constant").unwrap();
+        assert_eq!(t.to_bytes(), b"constant");
+
+        let nul = "x\0";
+        assert!(try_format_c!("{}", nul).is_err());
+    }
+
+    #[test]
+    fn test_clone() {
+        let long = gen_string(STRING_SIZE * 2);
+
+        for s in ["short", &long] {
+            let t = CFixedString::from_str(s);
+            let copy = t.clone();
+            drop(t);
+
+            assert_eq!(copy.to_bytes(), s.as_bytes());
+            assert_eq!(copy.is_allocated(), s.len() >= STRING_SIZE);
+        }
+
+        let t = CFixedString::from_static("static\0");
+        let copy = t.clone();
+        assert!(copy.is_static());
+        assert_eq!(copy.as_ptr(), t.as_ptr());
+    }
+
+    #[test]
+    fn test_default() {
+        #[derive(Default)]
+        struct State {
+            name: CFixedString,
+        }
+
+        let mut state = State::default();
+        assert!(state.name.is_empty());
+
+        state.name = CFixedString::from_str(gen_string(STRING_SIZE * 2));
+        let name = mem::take(&mut state.name);
+        assert_eq!(name.to_bytes().len(), STRING_SIZE * 2);
+        assert!(state.name.is_empty());
+    }
+
+    #[test]
+    fn test_debug() {
+        let t = CFixedString::from_str("tab\t\"quoted\"");
+        assert_eq!(
+            format!("{:?}", t),
+            r#"CFixedString("tab\t\"quoted\"", inline)"#
+        );
+
+        let t = CFixedString::from_str(gen_string(STRING_SIZE));
+        assert!(format!("{:?}", t).ends_with("\", heap)"));
+
+        let t = CFixedString::from_static("\u{e5}\0");
+        assert_eq!(format!("{:?}", t), "CFixedString(\"\u{e5}\", static)");
+
+        if STRING_SIZE != 24 {
+            let t = CFixedStringN::<24>::from_str("sized");
+            assert_eq!(format!("{:?}", t), r#"CFixedStringN<24>("sized", inline)"#);
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let t = CFixedString::from_str("shown");
+        assert_eq!(format!("[{}] [{:>7}]", t, t), "[shown] [  shown]");
+
+        let mut t = CFixedString::new();
+        t.push_bytes_utf8_partial(b"ok\xffok");
+        assert_eq!(format!("{}", t), "ok\u{fffd}ok");
+    }
+
+    #[test]
+    fn test_eq_strings() {
+        let long = gen_string(STRING_SIZE * 2);
+        let t = CFixedString::from_str(&long);
+        let c = CString::new(long.as_str()).unwrap();
+
+        assert_eq!(t, long.as_str());
+        assert_eq!(t, *long.as_str());
+        assert_eq!(t, long);
+        assert_eq!(t, c);
+        assert_eq!(t, c.as_c_str());
+        assert_eq!(t, *c.as_c_str());
+        assert_eq!(long, t);
+        assert_eq!(c, t);
+        assert_eq!(c.as_c_str(), t);
+
+        let t = CFixedString::from_str("short");
+        assert_eq!("short", t);
+        assert_ne!(t, "shorter");
+        assert_ne!(t, CString::new("shor").unwrap());
+        assert_eq!(t, CFixedStringN::<4>::from_str("short"));
+    }
+
+    #[test]
+    fn test_parse() {
+        let t: CFixedString = "gl_Position".parse().unwrap();
+        assert_eq!(t, "gl_Position");
+
+        let long = gen_string(STRING_SIZE * 2);
+        assert_eq!(long.parse::<CFixedStringN<8>>().unwrap(), long);
+
+        assert_eq!(
+            "a\0b".parse::<CFixedString>().unwrap_err(),
+            Error::InteriorNul { position: 1 }
+        );
+    }
+
+    #[test]
+    fn test_collect() {
+        let t: CFixedString = "a-b-c".chars().filter(|&c| c != '-').collect();
+        assert_eq!(t, "abc");
+
+        let t: CFixedStringN<8> = ["uniform", " ", "vec4"].iter().copied().collect();
+        assert!(t.is_allocated());
+        assert_eq!(t, "uniform vec4");
+
+        let mut t = CFixedString::from_str("gr");
+        t.extend("\u{f6}\u{df}e".chars());
+        t.extend(std::iter::repeat("!").take(STRING_SIZE));
+        assert!(t.to_bytes().starts_with("gr\u{f6}\u{df}e!".as_bytes()));
+        assert_eq!(t.to_bytes().len(), 7 + STRING_SIZE);
+    }
+
+    #[test]
+    fn test_add() {
+        let suffix = CFixedStringN::<4>::from_str("_ptr");
+        let t = CFixedString::from_str("get") + "_proc" + &suffix;
+        assert_eq!(t, "get_proc_ptr");
+
+        let mut t = CFixedString::from_str("head");
+        t += &CFixedString::from_str(gen_string(STRING_SIZE));
+        t += "!";
+        assert!(t.is_allocated());
+        assert_eq!(t.to_bytes().len(), 5 + STRING_SIZE);
+    }
+
+    #[test]
+    fn test_as_ref_bytes() {
+        fn checksum(bytes: impl AsRef<[u8]>) -> u32 {
+            bytes.as_ref().iter().map(|&b| b as u32).sum()
+        }
+
+        let t = CFixedString::from_str("abc");
+        assert_eq!(checksum(&t), checksum(b"abc"));
+        assert_eq!(AsRef::<[u8]>::as_ref(&t).as_ptr(), t.as_ptr() as *const u8);
+    }
+
+    #[test]
+    fn test_index() {
+        let t = CFixedString::from_str("frame=42 time=1.5ms");
+        assert_eq!(&t[6..8], "42");
+        assert_eq!(&t[9..], "time=1.5ms");
+        assert_eq!(&t[..=4], "frame");
+        assert_eq!(&t[..], "frame=42 time=1.5ms");
+
+        let long = gen_string(STRING_SIZE * 2);
+        let t = CFixedString::from_str(&long);
+        assert_eq!(&t[STRING_SIZE..], &long[STRING_SIZE..]);
+    }
+
+    #[test]
+    #[should_panic(expected = "char boundary")]
+    fn test_index_inside_char() {
+        let t = CFixedString::from_str("\u{e5}");
+        let _ = &t[..1];
+    }
 }
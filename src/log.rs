@@ -0,0 +1,98 @@
+use crate::CFixedString;
+use core::ffi::c_char;
+use std::cell::RefCell;
+use std::fmt::{self, Write};
+
+thread_local! {
+    static LOG_BUFFER: RefCell<CFixedString> = RefCell::new(CFixedString::new());
+}
+
+#[doc(hidden)]
+pub fn __log_c<L>(logger: extern "C" fn(L, *const c_char), level: L, args: fmt::Arguments<'_>) {
+    let mut level = Some(level);
+
+    let used_buffer = LOG_BUFFER
+        .try_with(|buf| match buf.try_borrow_mut() {
+            Ok(mut buf) => {
+                // Cleared in place, so a buffer that moved to the heap for a
+                // long message keeps its capacity for the next ones
+                buf.truncate_bytes(0);
+                let _ = buf.write_fmt(args);
+                logger(level.take().unwrap(), buf.as_ptr());
+                true
+            }
+            Err(_) => false,
+        })
+        .unwrap_or(false);
+
+    // The logger logged from inside the callback, or the thread is shutting
+    // down, so format on the stack instead.
+    if !used_buffer {
+        let mut buf = CFixedString::new();
+        let _ = buf.write_fmt(args);
+        logger(level.take().unwrap(), buf.as_ptr());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::log_c;
+    use core::ffi::CStr;
+    use core::ffi::{c_char, c_int};
+    use std::cell::RefCell;
+
+    thread_local! {
+        static LOGGED: RefCell<Vec<(c_int, String)>> = const { RefCell::new(Vec::new()) };
+    }
+
+    extern "C" fn logger(level: c_int, msg: *const c_char) {
+        let msg = unsafe { CStr::from_ptr(msg) }.to_str().unwrap().to_owned();
+        LOGGED.with(|l| l.borrow_mut().push((level, msg)));
+
+        if level == 2 {
+            log_c!(logger, 1, "nested");
+        }
+    }
+
+    #[test]
+    fn test_log_c() {
+        log_c!(logger, 3, "value {} of {}", 1, "two");
+        log_c!(logger, 2, "no args");
+        let nul = String::from("a\0b");
+        log_c!(logger, 4, "cut {} here", nul);
+
+        LOGGED.with(|l| {
+            assert_eq!(
+                *l.borrow(),
+                vec![
+                    (3, "value 1 of two".to_owned()),
+                    (2, "no args".to_owned()),
+                    (1, "nested".to_owned()),
+                    (4, "cut ".to_owned())
+                ]
+            );
+        });
+    }
+
+    thread_local! {
+        static POINTERS: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+    }
+
+    extern "C" fn pointer_logger(_level: c_int, msg: *const c_char) {
+        POINTERS.with(|p| p.borrow_mut().push(msg as usize));
+    }
+
+    #[test]
+    fn test_log_c_keeps_heap_buffer() {
+        let long = "x".repeat(crate::STRING_SIZE * 2);
+
+        log_c!(pointer_logger, 0, "{}", long);
+        log_c!(pointer_logger, 0, "short");
+        log_c!(pointer_logger, 0, "{}", long);
+
+        POINTERS.with(|p| {
+            let p = p.borrow();
+            assert!(p[0] == p[1] && p[1] == p[2]);
+        });
+    }
+}
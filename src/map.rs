@@ -0,0 +1,304 @@
+use crate::{CFixedStringN, Error};
+use core::ffi::c_char;
+use core::ffi::CStr;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+// Hashes and compares by the bytes without the terminator so the table can
+// be searched with a plain `&[u8]` taken from any kind of C string.
+struct Key<const N: usize>(CFixedStringN<N>);
+
+impl<const N: usize> Hash for Key<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bytes().hash(state)
+    }
+}
+
+impl<const N: usize> PartialEq for Key<N> {
+    fn eq(&self, other: &Key<N>) -> bool {
+        self.0.to_bytes() == other.0.to_bytes()
+    }
+}
+
+impl<const N: usize> Eq for Key<N> {}
+
+impl<const N: usize> Borrow<[u8]> for Key<N> {
+    fn borrow(&self) -> &[u8] {
+        self.0.to_bytes()
+    }
+}
+
+/// A hash map keyed by short C strings.
+///
+/// Keys are stored as `CFixedStringN<N>`s right in the table, so unlike a
+/// `HashMap<CString, V>` keys shorter than `N` bytes don't need a heap
+/// allocation each, and longer ones go to the heap. `N` is 32 unless named
+/// in the type, which a bare `CFixedStringMap::new()` doesn't do:
+///
+/// ```
+/// use cfixed_string::CFixedStringMap;
+///
+/// let mut small: CFixedStringMap<u32> = CFixedStringMap::new();
+/// small.insert("width", 640);
+///
+/// let mut large = CFixedStringMap::<u32, 128>::new();
+/// large.insert("a_much_longer_key_than_usual", 1);
+/// ```
+///
+/// Lookups take a `&str`, a `&CStr` or a raw pointer handed over by a C
+/// callback, without converting it to an owned key first.
+pub struct CFixedStringMap<V, const N: usize = 32> {
+    map: HashMap<Key<N>, V>,
+}
+
+impl<V, const N: usize> CFixedStringMap<V, N> {
+    /// Creates an empty map
+    pub fn new() -> Self {
+        CFixedStringMap {
+            map: HashMap::new(),
+        }
+    }
+
+    /// Creates an empty map with room for at least `capacity` entries
+    pub fn with_capacity(capacity: usize) -> Self {
+        CFixedStringMap {
+            map: HashMap::with_capacity(capacity),
+        }
+    }
+
+    /// Inserts `value` under `key`, returning the previous value if the key
+    /// was already present.
+    ///
+    /// # Panics
+    ///
+    /// Panics like `CFixedStringN::from_str` if `key` has a zero byte that
+    /// `NulPolicy` rejects, which it does by default, or if the heap budget
+    /// rejects a long key. `try_insert` returns those as errors instead.
+    pub fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        self.map.insert(Key(CFixedStringN::from_str(key)), value)
+    }
+
+    /// Same as `insert`, but fails with the position of the first zero byte
+    /// in `key` whatever the `NulPolicy` is, or if the heap budget rejects
+    /// the key.
+    pub fn try_insert(&mut self, key: &str, value: V) -> Result<Option<V>, Error> {
+        Ok(self
+            .map
+            .insert(Key(CFixedStringN::try_from_str(key)?), value))
+    }
+
+    /// Returns the value stored under `key`
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.map.get(key.as_bytes())
+    }
+
+    /// Returns the value stored under `key`
+    pub fn get_cstr(&self, key: &CStr) -> Option<&V> {
+        self.map.get(key.to_bytes())
+    }
+
+    /// Returns the value stored under the C string at `key`.
+    ///
+    /// # Safety
+    ///
+    /// `key` must point to a valid zero terminated string, as for
+    /// `CStr::from_ptr`.
+    pub unsafe fn get_ptr(&self, key: *const c_char) -> Option<&V> {
+        self.get_cstr(CStr::from_ptr(key))
+    }
+
+    /// Returns a mutable reference to the value stored under `key`
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        self.map.get_mut(key.as_bytes())
+    }
+
+    /// Returns true if `key` is present
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.map.contains_key(key.as_bytes())
+    }
+
+    /// Removes `key`, returning its value if it was present
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        self.map.remove(key.as_bytes())
+    }
+
+    /// Returns the number of entries
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if the map has no entries
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Removes all entries, keeping the allocated table
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+    /// Returns an iterator over the entries in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = (&CFixedStringN<N>, &V)> {
+        self.map.iter().map(|(key, value)| (&key.0, value))
+    }
+}
+
+impl<V, const N: usize> Default for CFixedStringMap<V, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A hash set of short C strings, see `CFixedStringMap` for what `N` is.
+#[derive(Default)]
+pub struct CFixedStringSet<const N: usize = 32> {
+    map: CFixedStringMap<(), N>,
+}
+
+impl<const N: usize> CFixedStringSet<N> {
+    /// Creates an empty set
+    pub fn new() -> Self {
+        CFixedStringSet {
+            map: CFixedStringMap::new(),
+        }
+    }
+
+    /// Creates an empty set with room for at least `capacity` strings
+    pub fn with_capacity(capacity: usize) -> Self {
+        CFixedStringSet {
+            map: CFixedStringMap::with_capacity(capacity),
+        }
+    }
+
+    /// Adds `value`, returning false if it was already present.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as `CFixedStringMap::insert`.
+    pub fn insert(&mut self, value: &str) -> bool {
+        self.map.insert(value, ()).is_none()
+    }
+
+    /// Same as `insert`, but fails instead of panicking like
+    /// `CFixedStringMap::try_insert`
+    pub fn try_insert(&mut self, value: &str) -> Result<bool, Error> {
+        Ok(self.map.try_insert(value, ())?.is_none())
+    }
+
+    /// Returns true if `value` is present
+    pub fn contains(&self, value: &str) -> bool {
+        self.map.contains_key(value)
+    }
+
+    /// Returns true if `value` is present
+    pub fn contains_cstr(&self, value: &CStr) -> bool {
+        self.map.get_cstr(value).is_some()
+    }
+
+    /// Returns true if the C string at `value` is present.
+    ///
+    /// # Safety
+    ///
+    /// `value` must point to a valid zero terminated string, as for
+    /// `CStr::from_ptr`.
+    pub unsafe fn contains_ptr(&self, value: *const c_char) -> bool {
+        self.map.get_ptr(value).is_some()
+    }
+
+    /// Removes `value`, returning true if it was present
+    pub fn remove(&mut self, value: &str) -> bool {
+        self.map.remove(value).is_some()
+    }
+
+    /// Returns the number of strings
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Removes all strings, keeping the allocated table
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+    /// Returns an iterator over the strings in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = &CFixedStringN<N>> {
+        self.map.iter().map(|(key, _)| key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem;
+
+    #[test]
+    fn test_map_lookups() {
+        let mut map: CFixedStringMap<i32> = CFixedStringMap::new();
+
+        assert_eq!(map.insert("width", 1), None);
+        assert_eq!(map.insert("height", 2), None);
+        assert_eq!(map.insert("width", 3), Some(1));
+        assert_eq!(map.len(), 2);
+
+        let c_key = CStr::from_bytes_with_nul(b"height\0").unwrap();
+
+        assert_eq!(map.get("width"), Some(&3));
+        assert_eq!(map.get_cstr(c_key), Some(&2));
+        assert_eq!(unsafe { map.get_ptr(c_key.as_ptr()) }, Some(&2));
+        assert_eq!(map.get("depth"), None);
+
+        *map.get_mut("height").unwrap() += 10;
+        assert_eq!(map.remove("height"), Some(12));
+        assert!(!map.contains_key("height"));
+
+        let keys: Vec<_> = map.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+        assert_eq!(keys, vec![("width".into(), 3)]);
+    }
+
+    #[test]
+    fn test_set() {
+        let mut set = CFixedStringSet::<8>::new();
+        let long = "k".repeat(crate::STRING_SIZE * 2);
+
+        assert!(set.insert("GL_ARB_debug_output"));
+        assert!(set.insert(&long));
+        assert!(!set.insert("GL_ARB_debug_output"));
+
+        let ext = CStr::from_bytes_with_nul(b"GL_ARB_debug_output\0").unwrap();
+
+        assert!(set.contains_cstr(ext));
+        assert!(unsafe { set.contains_ptr(ext.as_ptr()) });
+        assert!(set.contains(&long));
+        assert!(set.remove(&long));
+        assert_eq!(set.iter().count(), 1);
+
+        assert!(set.iter().all(|s| s.is_allocated()));
+        assert!(set.try_insert("short").unwrap());
+        assert!(set.contains("short"));
+        assert!(!set
+            .iter()
+            .find(|s| s.to_bytes() == b"short")
+            .unwrap()
+            .is_allocated());
+        assert_eq!(
+            set.try_insert("a\0b"),
+            Err(Error::InteriorNul { position: 1 })
+        );
+    }
+
+    #[test]
+    fn test_key_size() {
+        // The keys stay small whatever `STRING_SIZE` is
+        assert!(mem::size_of::<Key<32>>() < 64);
+
+        let mut map = CFixedStringMap::<u8, 64>::with_capacity(4);
+        assert_eq!(map.try_insert("x", 1), Ok(None));
+        assert_eq!(map.try_insert("x", 2), Ok(Some(1)));
+    }
+}
@@ -0,0 +1,98 @@
+use crate::CFixedStringN;
+
+/// A length saved by `CFixedString::checkpoint`, to go back to with
+/// `CFixedString::rollback_to`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mark {
+    len: usize,
+}
+
+impl Mark {
+    /// Returns the saved length in bytes, excluding the terminator
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the mark was taken on an empty string
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Remembers the current length, so a prefix such as a directory can
+    /// be reused for many different suffixes without formatting it again.
+    pub fn checkpoint(&self) -> Mark {
+        Mark {
+            len: self.to_bytes().len(),
+        }
+    }
+
+    /// Drops everything appended since `mark` was taken and re-terminates
+    /// the string.
+    ///
+    /// A mark that falls inside a character, which can only happen when it
+    /// was taken on a different string, cuts before that character.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string has been shortened below the mark since.
+    pub fn rollback_to(&mut self, mark: Mark) {
+        let len = self.to_bytes().len();
+
+        assert!(
+            mark.len <= len,
+            "mark at {} is past the end of the string ({})",
+            mark.len,
+            len
+        );
+
+        self.truncate_bytes(mark.len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CFixedString;
+    use std::fmt::Write;
+
+    #[test]
+    fn test_checkpoint_rollback() {
+        let mut path = CFixedString::from_str("/usr/share/");
+        let mark = path.checkpoint();
+        assert_eq!(mark.len(), 11);
+
+        for name in &["fonts", "icons", "themes"] {
+            path.write_str(name).unwrap();
+            assert!(path.to_string().ends_with(name));
+
+            path.rollback_to(mark);
+            assert_eq!(path.to_bytes_with_nul(), b"/usr/share/\0");
+        }
+
+        // Spilling to the heap in between works the same
+        path.write_str(&"x".repeat(crate::STRING_SIZE)).unwrap();
+        path.rollback_to(mark);
+        assert_eq!(&path.to_string(), "/usr/share/");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rollback_past_end() {
+        let mut t = CFixedString::from_str("abc");
+        let mark = t.checkpoint();
+
+        t.truncate_to_char_boundary(1);
+        t.rollback_to(mark);
+    }
+
+    #[test]
+    fn test_rollback_keeps_utf8() {
+        let other = CFixedString::from_str("ab");
+        let mark = other.checkpoint();
+
+        let mut t = CFixedString::from_str("aé");
+        t.rollback_to(mark);
+        assert_eq!(t.to_bytes_with_nul(), b"a\0");
+    }
+}
@@ -0,0 +1,143 @@
+use crate::fixed_buf::{fixed_buf_api, FixedBuf};
+use crate::Error;
+
+/// A C string that lives entirely in an inline buffer of `N` bytes and
+/// never allocates.
+///
+/// Writes that don't fit fail with `CapacityExceeded` and leave the string
+/// unchanged instead of moving it to the heap, for hard-realtime code where
+/// any allocation is forbidden.
+pub struct CFixedStringNoAlloc<const N: usize> {
+    s: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> CFixedStringNoAlloc<N> {
+    /// The longest string, excluding the terminator, that fits
+    pub const CAPACITY: usize = N - 1;
+
+    // Checked in `new` so a buffer without room for the terminator fails to
+    // compile
+    const HAS_TERMINATOR: () = assert!(N > 0, "the buffer needs room for the terminator");
+
+    /// Creates an empty string
+    pub fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::HAS_TERMINATOR;
+
+        CFixedStringNoAlloc { s: [0; N], len: 0 }
+    }
+
+    /// Creates a string from `s`, failing if it doesn't fit or contains a
+    /// zero byte rejected by `NulPolicy`
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self, Error> {
+        let mut t = Self::new();
+        t.push_str(s)?;
+        Ok(t)
+    }
+
+    /// Appends `s` as a whole, or fails and leaves the string unchanged if
+    /// it doesn't fit or contains a zero byte rejected by `NulPolicy`
+    pub fn push_str(&mut self, s: &str) -> Result<(), Error> {
+        self.push(s, false)
+    }
+
+    /// Appends `byte` until the string is `width` bytes long, excluding the
+    /// terminator, like `CFixedString::pad_to`.
+    ///
+    /// Fails with `CapacityExceeded` and leaves the string unchanged if
+    /// `width` doesn't fit, or with `InteriorNul` for a zero `byte` even
+    /// when no padding is needed.
+    pub fn pad_to(&mut self, width: usize, byte: u8) -> Result<(), Error> {
+        assert!(byte.is_ascii(), "padding byte {:#x} is not ASCII", byte);
+
+        if byte == 0 {
+            return Err(Error::InteriorNul { position: 0 });
+        }
+
+        if width <= self.len {
+            return Ok(());
+        }
+
+        if width > Self::CAPACITY {
+            return Err(Error::CapacityExceeded { needed: width + 1 });
+        }
+
+        for b in &mut self.s[self.len..width] {
+            *b = byte;
+        }
+
+        self.s[width] = 0;
+        self.len = width;
+        Ok(())
+    }
+}
+
+impl<const N: usize> FixedBuf for CFixedStringNoAlloc<N> {
+    fn buf(&self) -> &[u8] {
+        &self.s
+    }
+
+    fn buf_mut(&mut self) -> &mut [u8] {
+        &mut self.s
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn set_len(&mut self, len: usize) {
+        self.len = len;
+    }
+}
+
+fixed_buf_api!(impl[const N: usize] CFixedStringNoAlloc<N>);
+
+impl<const N: usize> Default for CFixedStringNoAlloc<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write;
+
+    #[test]
+    fn test_no_alloc() {
+        let mut t = CFixedStringNoAlloc::<8>::from_str("abc").unwrap();
+
+        write!(&mut t, "{}", 1234).unwrap();
+        assert_eq!(t.as_str(), "abc1234");
+        assert_eq!(t.len(), CFixedStringNoAlloc::<8>::CAPACITY);
+
+        assert_eq!(t.push_str("x"), Err(Error::CapacityExceeded { needed: 9 }));
+        assert!(write!(&mut t, "{}", 5).is_err());
+        assert_eq!(t.to_bytes_with_nul(), b"abc1234\0");
+
+        t.clear();
+        assert_eq!(t.push_str("a\0"), Err(Error::InteriorNul { position: 1 }));
+        assert!(t.is_empty());
+
+        assert!(CFixedStringNoAlloc::<4>::from_str("long").is_err());
+    }
+
+    #[test]
+    fn test_pad_to() {
+        let mut t = CFixedStringNoAlloc::<8>::from_str("ID").unwrap();
+        t.pad_to(5, b' ').unwrap();
+        assert_eq!(t.to_bytes_with_nul(), b"ID   \0");
+
+        assert_eq!(
+            t.pad_to(8, b' '),
+            Err(Error::CapacityExceeded { needed: 9 })
+        );
+        assert_eq!(t.pad_to(6, 0), Err(Error::InteriorNul { position: 0 }));
+        assert_eq!(t.as_str(), "ID   ");
+
+        t.pad_to(7, b'-').unwrap();
+        assert_eq!(t.as_str(), "ID   --");
+    }
+}
@@ -0,0 +1,41 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// What to do with zero bytes written into a `CFixedString`, set with
+/// `set_nul_policy`.
+///
+/// C would see a zero byte as the end of the string, so it can't be passed
+/// through as is. The policy applies to every write, whether the string is
+/// inline or on the heap, and also to `CStrBuf`, `CFixedStringNoAlloc`,
+/// `CStorageString` and `CFixedStringIn`. Only `io_writer` always rejects
+/// zero bytes, as dropping or replacing part of a byte stream would corrupt
+/// it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NulPolicy {
+    /// Fail the write with `Error::InteriorNul` and leave the string as it
+    /// was, which is the default
+    Reject,
+    /// Keep the part of the write before the first zero byte and drop the
+    /// rest of it
+    Truncate,
+    /// Write each zero byte as `U+FFFD REPLACEMENT CHARACTER`
+    Replace,
+}
+
+static NUL_POLICY: AtomicU8 = AtomicU8::new(NulPolicy::Reject as u8);
+
+/// Sets the process wide policy for zero bytes in written strings.
+///
+/// Infallible APIs, such as `From<&str>`, panic when the `Reject` policy
+/// rejects a write.
+pub fn set_nul_policy(policy: NulPolicy) {
+    NUL_POLICY.store(policy as u8, Ordering::Relaxed);
+}
+
+/// Returns the policy set with `set_nul_policy`
+pub fn nul_policy() -> NulPolicy {
+    match NUL_POLICY.load(Ordering::Relaxed) {
+        0 => NulPolicy::Reject,
+        1 => NulPolicy::Truncate,
+        _ => NulPolicy::Replace,
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{CFixedStringN, Error};
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Appends `value` written in `radix`, for register dumps, octal
+    /// permission masks or base-36 ids.
+    ///
+    /// Digits above 9 use letters, upper case if `uppercase` is set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radix` is not in the range 2 to 36.
+    pub fn push_radix(
+        &mut self,
+        value: impl Into<u128>,
+        radix: u32,
+        uppercase: bool,
+    ) -> Result<(), Error> {
+        assert!(
+            (2..=36).contains(&radix),
+            "radix must be between 2 and 36, got {}",
+            radix
+        );
+
+        let mut value = value.into();
+        let radix = u128::from(radix);
+        let letters = if uppercase { b'A' } else { b'a' };
+
+        // Enough for u128::MAX in base 2
+        let mut digits = [0; 128];
+        let mut start = digits.len();
+
+        loop {
+            let digit = (value % radix) as u8;
+
+            start -= 1;
+            digits[start] = if digit < 10 {
+                b'0' + digit
+            } else {
+                letters + digit - 10
+            };
+
+            value /= radix;
+
+            if value == 0 {
+                break;
+            }
+        }
+
+        self.push_bytes(&digits[start..])
+    }
+
+    /// Appends `value` in decimal with `separator` between each group of
+    /// three digits, such as `-1,234,567`.
+    pub fn push_int_grouped(
+        &mut self,
+        value: impl Into<i128>,
+        separator: char,
+    ) -> Result<(), Error> {
+        let value = value.into();
+        let mut sep = [0; 4];
+        let sep = separator.encode_utf8(&mut sep).as_bytes();
+
+        // 39 digits for i128::MIN, 12 separators of up to 4 bytes and the sign
+        let mut out = [0; 39 + 12 * 4 + 1];
+        let mut start = out.len();
+        let mut magnitude = value.unsigned_abs();
+        let mut count = 0;
+
+        loop {
+            if count != 0 && count % 3 == 0 {
+                start -= sep.len();
+                out[start..start + sep.len()].copy_from_slice(sep);
+            }
+
+            start -= 1;
+            out[start] = b'0' + (magnitude % 10) as u8;
+            magnitude /= 10;
+            count += 1;
+
+            if magnitude == 0 {
+                break;
+            }
+        }
+
+        if value < 0 {
+            start -= 1;
+            out[start] = b'-';
+        }
+
+        self.push_bytes(&out[start..])
+    }
+
+    /// Appends `value` with exactly `decimals` digits after the point,
+    /// the same as `{:.N}` in `format!`.
+    pub fn push_f64_fixed(&mut self, value: f64, decimals: usize) -> Result<(), Error> {
+        self.try_write_fmt(format_args!("{:.*}", decimals, value))
+    }
+
+    /// Appends `value` in scientific notation such as `1.5e3`, the same as
+    /// `{:e}` in `format!`.
+    pub fn push_f64_exp(&mut self, value: f64) -> Result<(), Error> {
+        self.try_write_fmt(format_args!("{:e}", value))
+    }
+
+    /// Appends the shortest decimal form of `value` that parses back to the
+    /// same `f64`.
+    pub fn push_f64_shortest(&mut self, value: f64) -> Result<(), Error> {
+        self.try_write_fmt(format_args!("{}", value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CFixedString;
+
+    #[test]
+    fn test_push_radix() {
+        let mut t = CFixedString::new();
+
+        t.push_radix(0o755u32, 8, false).unwrap();
+        t.push_bytes(b" ").unwrap();
+        t.push_radix(0xdead_beefu32, 16, true).unwrap();
+        t.push_bytes(b" ").unwrap();
+        t.push_radix(5u8, 2, false).unwrap();
+        t.push_bytes(b" ").unwrap();
+        t.push_radix(35u64 * 36 + 10, 36, false).unwrap();
+        t.push_bytes(b" ").unwrap();
+        t.push_radix(0u8, 10, false).unwrap();
+
+        assert_eq!(&t.to_string(), "755 DEADBEEF 101 za 0");
+
+        let mut t = CFixedString::new();
+        t.push_radix(u128::MAX, 2, false).unwrap();
+        assert_eq!(t.to_bytes(), &[b'1'; 128][..]);
+    }
+
+    #[test]
+    fn test_push_int_grouped() {
+        let mut t = CFixedString::new();
+
+        t.push_int_grouped(1234567u32, ',').unwrap();
+        t.push_bytes(b" ").unwrap();
+        t.push_int_grouped(-1000i32, '.').unwrap();
+        t.push_bytes(b" ").unwrap();
+        t.push_int_grouped(999u16, ',').unwrap();
+        t.push_bytes(b" ").unwrap();
+        t.push_int_grouped(0u8, ',').unwrap();
+        t.push_bytes(b" ").unwrap();
+        t.push_int_grouped(12345678i64, '\u{202f}').unwrap();
+
+        assert_eq!(
+            &t.to_string(),
+            "1,234,567 -1.000 999 0 12\u{202f}345\u{202f}678"
+        );
+
+        let mut t = CFixedString::new();
+        t.push_int_grouped(i128::MIN, '\u{10ffff}').unwrap();
+        assert_eq!(
+            t.to_string().replace('\u{10ffff}', ","),
+            "-170,141,183,460,469,231,731,687,303,715,884,105,728"
+        );
+    }
+
+    #[test]
+    fn test_push_f64() {
+        let mut t = CFixedString::new();
+
+        t.push_f64_fixed(1.23456, 2).unwrap();
+        t.push_bytes(b" ").unwrap();
+        t.push_f64_fixed(2.5, 0).unwrap();
+        t.push_bytes(b" ").unwrap();
+        t.push_f64_exp(1500.0).unwrap();
+        t.push_bytes(b" ").unwrap();
+        t.push_f64_shortest(0.1).unwrap();
+        t.push_bytes(b" ").unwrap();
+        t.push_f64_shortest(f64::NAN).unwrap();
+
+        assert_eq!(&t.to_string(), "1.23 2 1.5e3 0.1 NaN");
+    }
+}
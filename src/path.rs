@@ -0,0 +1,165 @@
+//! Helpers for Windows extended-length paths.
+//!
+//! Paths longer than `MAX_PATH` (260) only work with the wide Windows file
+//! APIs when they carry the `\\?\` prefix, which also turns off the usual
+//! normalization done by Windows. These helpers add and strip the prefix
+//! and do that normalization up front.
+
+use crate::{CFixedString, Error};
+
+/// Longest path the extended-length form supports, in UTF-16 code units
+/// including the terminator.
+pub const MAX_EXTENDED_PATH: usize = 32767;
+
+const VERBATIM: &str = r"\\?\";
+const VERBATIM_UNC: &str = r"\\?\UNC\";
+const DEVICE: &str = r"\\.\";
+
+/// Converts an absolute path into its `\\?\` (or `\\?\UNC\` for network
+/// shares) form.
+///
+/// Forward slashes are turned into backslashes and `.` and `..` components
+/// are resolved, since Windows doesn't do that for prefixed paths. Paths
+/// that already have the prefix are returned as is. Relative paths,
+/// including ones relative to the current drive such as `\dir`, can't use
+/// the prefix, so only their slashes are turned into backslashes. Resolving
+/// `..` without knowing what they are relative to would change what they
+/// point at. Device paths such as `\\.\pipe\name` aren't network shares and
+/// are also only given backslashes. Neither of those is an extended-length
+/// path, so `MAX_EXTENDED_PATH` isn't checked for them.
+pub fn to_extended(path: &str) -> Result<CFixedString, Error> {
+    let mut out = CFixedString::new();
+
+    if path.starts_with(VERBATIM) {
+        out.push_bytes(path.as_bytes())?;
+        return check_len(out);
+    }
+
+    let path = path.replace('/', r"\");
+
+    if path.starts_with(DEVICE) {
+        out.push_bytes(path.as_bytes())?;
+        return Ok(out);
+    }
+
+    let rest = if let Some(unc) = path.strip_prefix(r"\\") {
+        out.push_bytes(VERBATIM_UNC.as_bytes())?;
+        unc
+    } else if is_drive_absolute(&path) {
+        out.push_bytes(VERBATIM.as_bytes())?;
+        out.push_bytes(&path.as_bytes()[..3])?;
+        &path[3..]
+    } else {
+        out.push_bytes(path.as_bytes())?;
+        return Ok(out);
+    };
+
+    let mut components: Vec<&str> = Vec::new();
+
+    for component in rest.split('\\') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            c => components.push(c),
+        }
+    }
+
+    for (i, component) in components.iter().enumerate() {
+        if i != 0 {
+            out.push_bytes(b"\\")?;
+        }
+
+        out.push_bytes(component.as_bytes())?;
+    }
+
+    check_len(out)
+}
+
+/// Removes a `\\?\` or `\\?\UNC\` prefix, such as the ones returned by
+/// `GetFinalPathNameByHandleW`, so the path can be shown to users or passed
+/// to APIs that don't accept it.
+///
+/// The plain prefix is only removed in front of a drive such as `C:\`.
+/// Other prefixed paths, like `\\?\Volume{guid}\` or `\\?\GLOBALROOT\`,
+/// have no form without it and are returned as is.
+pub fn strip_extended(path: &str) -> Result<CFixedString, Error> {
+    let mut out = CFixedString::new();
+
+    if let Some(unc) = path.strip_prefix(VERBATIM_UNC) {
+        out.push_bytes(br"\\")?;
+        out.push_bytes(unc.as_bytes())?;
+    } else {
+        let path = match path.strip_prefix(VERBATIM) {
+            Some(rest) if is_drive_absolute(rest) => rest,
+            _ => path,
+        };
+
+        out.push_bytes(path.as_bytes())?;
+    }
+
+    Ok(out)
+}
+
+fn is_drive_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\'
+}
+
+fn check_len(path: CFixedString) -> Result<CFixedString, Error> {
+    let s: &str = path.as_ref();
+    let needed = s.encode_utf16().count() + 1;
+
+    if needed > MAX_EXTENDED_PATH {
+        return Err(Error::CapacityExceeded { needed });
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_extended() {
+        let ext = |p| to_extended(p).unwrap().to_string().into_owned();
+
+        assert_eq!(ext(r"C:\games\..\data/./level1"), r"\\?\C:\data\level1");
+        assert_eq!(ext(r"\\server\share\dir"), r"\\?\UNC\server\share\dir");
+        assert_eq!(ext(r"\\?\C:\as\is"), r"\\?\C:\as\is");
+        assert_eq!(ext("relative/dir"), r"relative\dir");
+        assert_eq!(ext(r"..\x"), r"..\x");
+        assert_eq!(ext(r"\root\x"), r"\root\x");
+        assert_eq!(ext("./a/../b"), r".\a\..\b");
+        assert_eq!(ext(r"C:\..\x"), r"\\?\C:\x");
+        assert_eq!(ext(r"\\.\pipe\foo"), r"\\.\pipe\foo");
+        assert_eq!(ext("//./C:/x"), r"\\.\C:\x");
+
+        // Only the prefixed form has a length limit
+        let long = format!(r"dir\{}", "a".repeat(MAX_EXTENDED_PATH));
+        assert_eq!(ext(&long), long);
+
+        let long = format!(r"C:\{}", "a".repeat(MAX_EXTENDED_PATH));
+        assert!(matches!(
+            to_extended(&long),
+            Err(Error::CapacityExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn test_strip_extended() {
+        let strip = |p| strip_extended(p).unwrap().to_string().into_owned();
+
+        assert_eq!(strip(r"\\?\C:\data"), r"C:\data");
+        assert_eq!(strip(r"\\?\UNC\server\share"), r"\\server\share");
+        assert_eq!(strip(r"C:\plain"), r"C:\plain");
+
+        // These have no form without the prefix
+        let volume = r"\\?\Volume{6ba2bbd4-4bd6-11e9-a3c1-806e6f6e6963}\dir";
+        assert_eq!(strip(volume), volume);
+        let device = r"\\?\GLOBALROOT\Device\HarddiskVolume1\dir";
+        assert_eq!(strip(device), device);
+    }
+}
@@ -0,0 +1,305 @@
+use crate::{CFixedStringN, Error};
+use core::ffi::c_char;
+use core::ffi::CStr;
+use std::{mem, ptr, slice};
+
+/// A pattern to search for, accepting the same kinds of needles as the
+/// `str` search methods: a `char`, a string, a slice of `char`s, or a
+/// `FnMut(char) -> bool` closure.
+pub trait Pattern {
+    /// Returns the byte range of the first match in `haystack`
+    fn find_in(&mut self, haystack: &str) -> Option<(usize, usize)>;
+}
+
+impl Pattern for char {
+    fn find_in(&mut self, haystack: &str) -> Option<(usize, usize)> {
+        #[cfg(feature = "memchr")]
+        let found = if self.is_ascii() {
+            memchr::memchr(*self as u8, haystack.as_bytes())
+        } else {
+            let mut utf8 = [0; 4];
+            memchr::memmem::find(haystack.as_bytes(), self.encode_utf8(&mut utf8).as_bytes())
+        };
+
+        #[cfg(not(feature = "memchr"))]
+        let found = haystack.find(*self);
+
+        found.map(|i| (i, i + self.len_utf8()))
+    }
+}
+
+impl Pattern for &str {
+    fn find_in(&mut self, haystack: &str) -> Option<(usize, usize)> {
+        // A match of a valid UTF-8 needle always starts on a char boundary,
+        // so searching the raw bytes gives the same result as `str::find`.
+        #[cfg(feature = "memchr")]
+        let found = memchr::memmem::find(haystack.as_bytes(), self.as_bytes());
+
+        #[cfg(not(feature = "memchr"))]
+        let found = haystack.find(*self);
+
+        found.map(|i| (i, i + self.len()))
+    }
+}
+
+impl Pattern for &String {
+    fn find_in(&mut self, haystack: &str) -> Option<(usize, usize)> {
+        self.as_str().find_in(haystack)
+    }
+}
+
+impl Pattern for &[char] {
+    fn find_in(&mut self, haystack: &str) -> Option<(usize, usize)> {
+        let chars = *self;
+        find_char(haystack, |c| chars.contains(&c))
+    }
+}
+
+impl<F: FnMut(char) -> bool> Pattern for F {
+    fn find_in(&mut self, haystack: &str) -> Option<(usize, usize)> {
+        find_char(haystack, self)
+    }
+}
+
+fn find_char(haystack: &str, mut f: impl FnMut(char) -> bool) -> Option<(usize, usize)> {
+    haystack
+        .char_indices()
+        .find(|&(_, c)| f(c))
+        .map(|(i, c)| (i, i + c.len_utf8()))
+}
+
+/// Iterator over the pieces of a `CFixedString` separated by a pattern,
+/// created by `CFixedString::split`.
+pub struct Split<'a, P> {
+    haystack: &'a str,
+    pat: P,
+    start: usize,
+    pos: usize,
+    finished: bool,
+}
+
+impl<'a, P: Pattern> Split<'a, P> {
+    pub(crate) fn new(haystack: &'a str, pat: P) -> Self {
+        Split {
+            haystack,
+            pat,
+            start: 0,
+            pos: 0,
+            finished: false,
+        }
+    }
+}
+
+impl<'a, P: Pattern> Iterator for Split<'a, P> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        if self.finished {
+            return None;
+        }
+
+        let found = if self.pos <= self.haystack.len() {
+            self.pat.find_in(&self.haystack[self.pos..])
+        } else {
+            None
+        };
+
+        match found {
+            Some((a, b)) => {
+                let (a, b) = (self.pos + a, self.pos + b);
+                let piece = &self.haystack[self.start..a];
+
+                self.start = b;
+                self.pos = b;
+
+                // Step past empty matches so the next search makes progress,
+                // which gives the same pieces as `str::split` does.
+                if a == b {
+                    self.pos += self.haystack[b..].chars().next().map_or(1, char::len_utf8);
+                }
+
+                Some(piece)
+            }
+            None => {
+                self.finished = true;
+                Some(&self.haystack[self.start..])
+            }
+        }
+    }
+}
+
+/// Iterator over the pieces of a `CFixedString` separated by a pattern,
+/// each copied into a scratch buffer and terminated so it can be passed to
+/// C directly. Created by `CFixedString::split_terminated`.
+pub struct SplitTerminated<'a, P> {
+    split: Split<'a, P>,
+    scratch: &'a mut [c_char],
+}
+
+impl<'a, P: Pattern> Iterator for SplitTerminated<'a, P> {
+    type Item = &'a CStr;
+
+    fn next(&mut self) -> Option<&'a CStr> {
+        let piece = self.split.next()?;
+
+        assert!(
+            piece.len() < self.scratch.len(),
+            "scratch buffer too small, the pattern matched an empty string"
+        );
+
+        let (dst, rest) = mem::take(&mut self.scratch).split_at_mut(piece.len() + 1);
+        self.scratch = rest;
+
+        unsafe {
+            let dst = dst.as_mut_ptr() as *mut u8;
+            ptr::copy_nonoverlapping(piece.as_ptr(), dst, piece.len());
+            *dst.add(piece.len()) = 0;
+
+            let bytes = slice::from_raw_parts(dst as *const u8, piece.len() + 1);
+            Some(CStr::from_bytes_until_nul(bytes).unwrap())
+        }
+    }
+}
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Returns the byte index of the first match of `pat`
+    pub fn find<P: Pattern>(&self, mut pat: P) -> Option<usize> {
+        pat.find_in(self.as_ref()).map(|(start, _)| start)
+    }
+
+    /// Returns true if `pat` matches somewhere in the string
+    pub fn contains<P: Pattern>(&self, pat: P) -> bool {
+        self.find(pat).is_some()
+    }
+
+    /// Returns an iterator over the pieces separated by `pat`, like
+    /// `str::split`
+    pub fn split<P: Pattern>(&self, pat: P) -> Split<'_, P> {
+        Split::new(self.as_ref(), pat)
+    }
+
+    /// Splits by `pat` into the strings in `out`, returning how many were
+    /// written.
+    ///
+    /// Like `str::splitn`, the last slot gets the unsplit remainder when
+    /// there are more pieces than slots.
+    pub fn split_to<P: Pattern>(&self, pat: P, out: &mut [Self]) -> usize {
+        let mut split = self.split(pat);
+        let mut count = 0;
+
+        while count + 1 < out.len() {
+            match split.next() {
+                Some(piece) => out[count] = Self::from(piece),
+                None => return count,
+            }
+
+            count += 1;
+        }
+
+        if !out.is_empty() && !split.finished {
+            out[count] = Self::from(&split.haystack[split.start..]);
+            count += 1;
+        }
+
+        count
+    }
+
+    /// Returns an iterator over the pieces separated by `pat`, each
+    /// terminated in `scratch` so it can be handed to C as is.
+    ///
+    /// `scratch` must be larger than the length of the string, which is
+    /// enough room for all pieces and their terminators.
+    ///
+    /// # Panics
+    ///
+    /// The iterator panics if `pat` matches the empty string, as the pieces
+    /// then need more room than the string itself.
+    pub fn split_terminated<'a, P: Pattern>(
+        &'a self,
+        pat: P,
+        scratch: &'a mut [c_char],
+    ) -> Result<SplitTerminated<'a, P>, Error> {
+        let needed = self.to_bytes().len() + 1;
+
+        if scratch.len() < needed {
+            return Err(Error::CapacityExceeded { needed });
+        }
+
+        Ok(SplitTerminated {
+            split: self.split(pat),
+            scratch,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CFixedString;
+
+    #[test]
+    fn test_find_contains() {
+        let t = CFixedString::from_str("device: gpu0, gpu1");
+
+        assert_eq!(t.find(':'), Some(6));
+        assert_eq!(t.find("gpu1"), Some(14));
+        assert_eq!(t.find(&[',', ' '][..]), Some(7));
+        assert_eq!(t.find(char::is_numeric), Some(11));
+        assert!(t.contains(&"gpu".to_owned()));
+        assert!(!t.contains("cpu"));
+
+        let t = CFixedString::from_str("größe → 3");
+        assert_eq!(t.find('→'), Some(8));
+        assert_eq!(t.find("ße"), Some(4));
+    }
+
+    #[test]
+    fn test_split() {
+        let t = CFixedString::from_str("a,b,,c");
+        let pieces: Vec<_> = t.split(',').collect();
+        assert_eq!(pieces, "a,b,,c".split(',').collect::<Vec<_>>());
+
+        let t = CFixedString::from_str("äb");
+        let pieces: Vec<_> = t.split("").collect();
+        assert_eq!(pieces, "äb".split("").collect::<Vec<_>>());
+
+        let t = CFixedString::from_str("x1y22z");
+        let pieces: Vec<_> = t.split(|c: char| c.is_ascii_digit()).collect();
+        assert_eq!(pieces, ["x", "y", "", "z"]);
+    }
+
+    #[test]
+    fn test_split_to() {
+        let t = CFixedString::from_str("usr:local:bin");
+        let mut out: Vec<_> = (0..4).map(|_| CFixedString::new()).collect();
+
+        assert_eq!(t.split_to(':', &mut out), 3);
+        assert_eq!(&out[2].to_string(), "bin");
+
+        assert_eq!(t.split_to(':', &mut out[..2]), 2);
+        assert_eq!(&out[0].to_string(), "usr");
+        assert_eq!(&out[1].to_string(), "local:bin");
+    }
+
+    #[test]
+    fn test_split_terminated() {
+        let t = CFixedString::from_str("GL_ARB_a GL_ARB_bb  GL_ARB_c");
+        let mut scratch = [0; 64];
+
+        let pieces: Vec<_> = t
+            .split_terminated(' ', &mut scratch)
+            .unwrap()
+            .map(|c| c.to_bytes_with_nul())
+            .collect();
+
+        assert_eq!(
+            pieces,
+            [&b"GL_ARB_a\0"[..], b"GL_ARB_bb\0", b"\0", b"GL_ARB_c\0"]
+        );
+
+        assert_eq!(
+            t.split_terminated(' ', &mut scratch[..8]).err(),
+            Some(Error::CapacityExceeded { needed: 29 })
+        );
+    }
+}
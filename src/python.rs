@@ -0,0 +1,72 @@
+use crate::{CFixedStringN, Error};
+use pyo3::exceptions::{PyMemoryError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyString;
+
+/// Extracts a Python `str`, storing short values inline.
+///
+/// Strings with a zero character raise `ValueError` as they can't be passed
+/// on to C, and a rejected heap budget raises `MemoryError`.
+impl<'py, const N: usize> FromPyObject<'py> for CFixedStringN<N> {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        let s = ob.downcast::<PyString>()?.to_cow()?;
+
+        if let Some(position) = s.find('\0') {
+            return Err(PyValueError::new_err(
+                Error::InteriorNul { position }.to_string(),
+            ));
+        }
+
+        let mut fixed = Self::new();
+        fixed
+            .push_bytes(s.as_bytes())
+            .map_err(|e| PyMemoryError::new_err(e.to_string()))?;
+
+        Ok(fixed)
+    }
+}
+
+/// Converts to a Python `str`, replacing invalid UTF-8 like `to_string`.
+impl<const N: usize> IntoPy<PyObject> for CFixedStringN<N> {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyString::new_bound(py, &self.to_string())
+            .into_any()
+            .unbind()
+    }
+}
+
+impl<const N: usize> IntoPy<PyObject> for &CFixedStringN<N> {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        PyString::new_bound(py, &self.to_string())
+            .into_any()
+            .unbind()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CFixedString;
+
+    #[test]
+    fn test_python_round_trip() {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            let short = PyString::new_bound(py, "from python");
+            let t: CFixedString = short.extract().unwrap();
+            assert!(!t.is_allocated());
+            assert_eq!(&t.to_string(), "from python");
+
+            let back: String = t.into_py(py).extract(py).unwrap();
+            assert_eq!(back, "from python");
+
+            let nul = PyString::new_bound(py, "a\0b");
+            let err = nul.extract::<CFixedString>().err().unwrap();
+            assert!(err.is_instance_of::<PyValueError>(py));
+
+            let not_str: PyObject = 12.into_py(py);
+            assert!(not_str.extract::<CFixedString>(py).is_err());
+        });
+    }
+}
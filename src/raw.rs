@@ -0,0 +1,56 @@
+use crate::CFixedStringN;
+use core::ffi::c_char;
+use core::ffi::c_void;
+
+/// A string handed over to C together with the function that frees it,
+/// matching the retain/release contract used by many C plugin APIs.
+///
+/// Created by `CFixedString::into_raw_with_destructor`.
+#[repr(C)]
+pub struct RetainedCString {
+    /// The zero terminated string, valid until `release` is called
+    pub ptr: *const c_char,
+    /// Frees the string, it must be called exactly once with `ctx`
+    pub release: unsafe extern "C" fn(ctx: *mut c_void),
+    /// The argument to pass to `release`
+    pub ctx: *mut c_void,
+}
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Moves the string into a box owned by C code.
+    ///
+    /// The returned pointer stays valid until C calls the returned release
+    /// function with the returned context pointer.
+    pub fn into_raw_with_destructor(self) -> RetainedCString {
+        // The pointer is taken after `into_raw`, as moving the box would
+        // invalidate one borrowed from it before
+        let ctx = Box::into_raw(Box::new(self));
+        let ptr = unsafe { (*ctx).as_ptr() };
+
+        RetainedCString {
+            ptr,
+            release: release_boxed::<N>,
+            ctx: ctx as *mut c_void,
+        }
+    }
+}
+
+unsafe extern "C" fn release_boxed<const N: usize>(ctx: *mut c_void) {
+    drop(Box::from_raw(ctx as *mut CFixedStringN<N>));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CFixedString;
+    use core::ffi::CStr;
+
+    #[test]
+    fn test_retain_release() {
+        let raw = CFixedString::from_str("retained").into_raw_with_destructor();
+
+        unsafe {
+            assert_eq!(CStr::from_ptr(raw.ptr).to_bytes(), b"retained");
+            (raw.release)(raw.ctx);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use crate::CFixedStringN;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+
+/// Serializes as a string, replacing invalid UTF-8 like `to_string`.
+impl<const N: usize> Serialize for CFixedStringN<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Deserializes from a string, storing short values inline.
+///
+/// Zero characters are handled by `NulPolicy` like any other write, so with
+/// the default `Reject` policy they fail deserialization.
+impl<'de, const N: usize> Deserialize<'de> for CFixedStringN<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(CFixedStringVisitor)
+    }
+}
+
+struct CFixedStringVisitor<const N: usize>;
+
+impl<'de, const N: usize> Visitor<'de> for CFixedStringVisitor<N> {
+    type Value = CFixedStringN<N>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a string")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<CFixedStringN<N>, E> {
+        let mut t = CFixedStringN::new();
+        t.push_bytes(s.as_bytes()).map_err(E::custom)?;
+        Ok(t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CFixedString, STRING_SIZE};
+
+    #[test]
+    fn test_serde_round_trip() {
+        let long = "d".repeat(STRING_SIZE * 2);
+
+        for s in ["shader_main", "\u{e5}\"quoted\"", &long] {
+            let json = serde_json::to_string(&CFixedString::from_str(s)).unwrap();
+            assert_eq!(json, serde_json::to_string(s).unwrap());
+
+            let t: CFixedString = serde_json::from_str(&json).unwrap();
+            assert_eq!(t, s);
+        }
+
+        let err = serde_json::from_str::<CFixedString>(r#""a\u0000b""#).unwrap_err();
+        assert!(err.to_string().contains("zero byte"));
+        assert!(serde_json::from_str::<CFixedString>("42").is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{CFixedStringN, Error};
+use std::convert::TryFrom;
+
+/// A pointer-free, fixed size string for shared memory and memory-mapped
+/// ring buffers read by another process.
+///
+/// The layout matches `struct { uint32_t len; char data[N]; }` in C. `data`
+/// is always zero terminated, so at most `N - 1` bytes of text fit.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct ShmString<const N: usize> {
+    len: u32,
+    data: [u8; N],
+}
+
+impl<const N: usize> ShmString<N> {
+    /// Returns the stored text without the terminator, or `None` if the
+    /// length field is out of range.
+    ///
+    /// Use `CFixedString::from_shm` to validate contents written by another
+    /// process.
+    pub fn to_bytes(&self) -> Option<&[u8]> {
+        self.data.get(..self.len as usize)
+    }
+}
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Copies the string into a fixed size shared memory form.
+    ///
+    /// Fails with `CapacityExceeded` if it doesn't fit in `M - 1` bytes.
+    pub fn to_shm<const M: usize>(&self) -> Result<ShmString<M>, Error> {
+        let bytes = self.to_bytes();
+        let needed = bytes.len() + 1;
+
+        if needed > M {
+            return Err(Error::CapacityExceeded { needed });
+        }
+
+        let mut shm = ShmString {
+            len: u32::try_from(bytes.len()).map_err(|_| Error::CapacityExceeded { needed })?,
+            data: [0; M],
+        };
+
+        shm.data[..bytes.len()].copy_from_slice(bytes);
+        Ok(shm)
+    }
+
+    /// Reads a string from its shared memory form.
+    ///
+    /// The other side can't be trusted, so the struct is copied before it
+    /// is checked and the length, the terminator, zero bytes and UTF-8 are
+    /// all validated.
+    pub fn from_shm<const M: usize>(shm: &ShmString<M>) -> Result<Self, Error> {
+        // Copy first so the writer can't change it between checking and use
+        let shm = *shm;
+        let len = shm.len as usize;
+
+        if len >= M {
+            return Err(Error::CapacityExceeded { needed: len + 1 });
+        }
+
+        let bytes = &shm.data[..len];
+
+        if let Some(position) = shm.data[..=len].iter().position(|&b| b == 0) {
+            if position != len {
+                return Err(Error::InteriorNul { position });
+            }
+        } else {
+            return Err(Error::CapacityExceeded { needed: len + 1 });
+        }
+
+        std::str::from_utf8(bytes)?;
+
+        let mut s = Self::new();
+        s.push_bytes(bytes)?;
+        Ok(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CFixedString;
+
+    #[test]
+    fn test_shm_round_trip() {
+        let shm = CFixedString::from_str("ring entry").to_shm::<16>().unwrap();
+        assert_eq!(shm.to_bytes(), Some(&b"ring entry"[..]));
+        assert_eq!(std::mem::size_of::<ShmString<16>>(), 20);
+
+        let t = CFixedString::from_shm(&shm).unwrap();
+        assert_eq!(&t.to_string(), "ring entry");
+
+        let full = CFixedString::from_str("0123456789abcdef").to_shm::<16>();
+        assert_eq!(full.err(), Some(Error::CapacityExceeded { needed: 17 }));
+    }
+
+    #[test]
+    fn test_shm_validation() {
+        let mut shm = CFixedString::from_str("abc").to_shm::<8>().unwrap();
+
+        shm.len = 8;
+        assert!(CFixedString::from_shm(&shm).is_err());
+
+        // Length points past the real terminator
+        shm.len = 5;
+        assert_eq!(
+            CFixedString::from_shm(&shm).err(),
+            Some(Error::InteriorNul { position: 3 })
+        );
+
+        // Missing terminator
+        shm.len = 3;
+        shm.data[3] = b'd';
+        assert!(CFixedString::from_shm(&shm).is_err());
+
+        shm.data = *b"\xffbc\0\0\0\0\0";
+        assert_eq!(
+            CFixedString::from_shm(&shm).err(),
+            Some(Error::InvalidUtf8 { offset: 0 })
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use crate::{CFixedStringN, STRING_SIZE};
+use core::ffi::{c_char, CStr};
+use std::ops;
+
+/// A `CFixedString` whose pointer stays valid when the value is moved,
+/// returned by `CFixedString::into_stable`.
+///
+/// An inline string lives inside the value, so moving it invalidates a
+/// pointer taken earlier with `as_ptr`. This keeps the string in a box and
+/// gives only shared access, so the pointer can be registered with C
+/// callbacks and stays the same until the value is dropped.
+pub struct StableCString<const N: usize = STRING_SIZE> {
+    inner: Box<CFixedStringN<N>>,
+}
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Moves the string into a box so its pointer survives moves, see
+    /// `StableCString`.
+    ///
+    /// An inline string is moved into a new allocation, heap and static
+    /// strings keep the pointer they already had.
+    pub fn into_stable(self) -> StableCString<N> {
+        StableCString {
+            inner: Box::new(self),
+        }
+    }
+}
+
+impl<const N: usize> StableCString<N> {
+    /// Returns the pointer to be passed down to the C code, which stays the
+    /// same for the lifetime of this value
+    pub fn as_ptr(&self) -> *const c_char {
+        self.inner.as_ptr()
+    }
+
+    /// Returns the string so it can be changed again, after which earlier
+    /// pointers must no longer be used
+    pub fn into_inner(self) -> CFixedStringN<N> {
+        *self.inner
+    }
+}
+
+impl<const N: usize> ops::Deref for StableCString<N> {
+    type Target = CStr;
+
+    fn deref(&self) -> &CStr {
+        &self.inner
+    }
+}
+
+impl<const N: usize> AsRef<CStr> for StableCString<N> {
+    fn as_ref(&self) -> &CStr {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CFixedString;
+
+    #[test]
+    fn test_stable_pointer() {
+        let long = "s".repeat(crate::STRING_SIZE * 2);
+
+        for s in ["short", &long] {
+            let stable = CFixedString::from_str(s).into_stable();
+            let ptr = stable.as_ptr();
+
+            let moved = Box::new(stable);
+            assert_eq!(moved.as_ptr(), ptr);
+            assert_eq!(moved.to_bytes(), s.as_bytes());
+        }
+
+        let stable = CFixedString::from_str("back").into_stable();
+        let mut t = stable.into_inner();
+        t.push_bytes(b"!").unwrap();
+        assert_eq!(t.to_bytes(), b"back!");
+    }
+}
@@ -0,0 +1,207 @@
+use crate::fixed_buf::{fixed_buf_api, FixedBuf};
+use crate::Error;
+
+/// A fixed size buffer that a `CStorageString` can be built in.
+///
+/// Implemented for arrays, slices, `Box<[u8]>` and `Vec<u8>`, as well as
+/// `arrayvec::ArrayVec` and `heapless::Vec` behind the features of the same
+/// name, so existing buffers can be used without copying.
+///
+/// # Safety
+///
+/// Once `as_bytes_mut` has been called, both methods must return the same
+/// memory with the same length every time, and its contents must only
+/// change through `as_bytes_mut`, as the string relies on its terminator
+/// and UTF-8 contents staying in place. Before that first call `as_bytes`
+/// may return less, which lets vectors fill themselves up lazily.
+pub unsafe trait Storage {
+    /// Returns the whole buffer, including room for the terminator
+    fn as_bytes(&self) -> &[u8];
+
+    /// Returns the whole buffer, including room for the terminator
+    fn as_bytes_mut(&mut self) -> &mut [u8];
+}
+
+unsafe impl<const N: usize> Storage for [u8; N] {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+unsafe impl Storage for &mut [u8] {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+unsafe impl Storage for Box<[u8]> {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+/// Uses the current length of the vector, which is never changed.
+unsafe impl Storage for Vec<u8> {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+/// Uses the full capacity, the vector is filled up by the first call to
+/// `as_bytes_mut`, which `CStorageString::new` makes.
+#[cfg(feature = "arrayvec")]
+unsafe impl<const N: usize> Storage for arrayvec::ArrayVec<u8, N> {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        while !self.is_full() {
+            self.push(0);
+        }
+
+        self
+    }
+}
+
+/// Uses the full capacity, the vector is filled up by the first call to
+/// `as_bytes_mut`, which `CStorageString::new` makes.
+#[cfg(feature = "heapless")]
+unsafe impl<const N: usize> Storage for heapless::Vec<u8, N> {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        // Can't fail as the new length is the capacity
+        let _ = self.resize(N, 0);
+        self
+    }
+}
+
+/// A C string built in a caller-chosen `Storage`.
+///
+/// Like `CFixedStringNoAlloc`, writes that don't fit fail and leave the
+/// string unchanged, as the storage can't grow.
+pub struct CStorageString<S: Storage> {
+    storage: S,
+    len: usize,
+}
+
+impl<S: Storage> CStorageString<S> {
+    /// Wraps `storage` and clears it to an empty string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `storage` is empty as there is no room for the terminator.
+    pub fn new(mut storage: S) -> Self {
+        // This has to be the first call into the storage, `as_bytes` only
+        // has a fixed length after it
+        let buf = storage.as_bytes_mut();
+        assert!(!buf.is_empty(), "storage needs room for the terminator");
+
+        buf[0] = 0;
+        CStorageString { storage, len: 0 }
+    }
+
+    /// Appends `s` as a whole, or fails and leaves the string unchanged if
+    /// it doesn't fit or contains a zero byte rejected by `NulPolicy`
+    pub fn push_str(&mut self, s: &str) -> Result<(), Error> {
+        self.push(s, false)
+    }
+
+    /// Returns the maximum length of the string, excluding the terminator
+    pub fn capacity(&self) -> usize {
+        self.storage.as_bytes().len() - 1
+    }
+
+    /// Returns the storage, with the string and its terminator at the start
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+}
+
+impl<S: Storage> FixedBuf for CStorageString<S> {
+    fn buf(&self) -> &[u8] {
+        self.storage.as_bytes()
+    }
+
+    fn buf_mut(&mut self) -> &mut [u8] {
+        self.storage.as_bytes_mut()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn set_len(&mut self, len: usize) {
+        self.len = len;
+    }
+}
+
+fixed_buf_api!(impl[S: Storage] CStorageString<S>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write;
+
+    #[test]
+    fn test_storage_types() {
+        let mut t = CStorageString::new([0xffu8; 8]);
+        write!(&mut t, "id={}", 42).unwrap();
+        assert_eq!(t.as_str(), "id=42");
+        assert_eq!(t.capacity(), 7);
+        assert!(t.push_str("abc").is_err());
+        assert_eq!(&t.into_inner()[..6], b"id=42\0");
+
+        let mut buf = [0u8; 4];
+        let mut t = CStorageString::new(&mut buf[..]);
+        t.push_str("abc").unwrap();
+        assert_eq!(t.to_bytes_with_nul(), b"abc\0");
+
+        let mut t = CStorageString::new(vec![0; 16]);
+        t.push_str("vec").unwrap();
+        assert_eq!(t.push_str("\0"), Err(Error::InteriorNul { position: 0 }));
+        t.clear();
+        assert!(t.is_empty());
+    }
+
+    #[cfg(feature = "arrayvec")]
+    #[test]
+    fn test_arrayvec() {
+        let mut t = CStorageString::new(arrayvec::ArrayVec::<u8, 8>::new());
+        t.push_str("arrayvec").err().unwrap();
+        t.push_str("array").unwrap();
+        assert_eq!(t.as_str(), "array");
+        assert_eq!(t.capacity(), 7);
+        assert_eq!(t.into_inner().len(), 8);
+    }
+
+    #[cfg(feature = "heapless")]
+    #[test]
+    fn test_heapless() {
+        let mut t = CStorageString::new(heapless::Vec::<u8, 8>::new());
+        t.push_str("heapless").err().unwrap();
+        t.push_str("heap").unwrap();
+        assert_eq!(t.as_str(), "heap");
+        assert_eq!(t.capacity(), 7);
+        assert_eq!(t.into_inner().len(), 8);
+    }
+}
@@ -0,0 +1,270 @@
+use crate::{check_no_nul, utf8_width, CFixedStringN, Error};
+use std::io::{self, Read};
+use std::str;
+
+/// An `io::Write` adapter that appends to a string, returned by
+/// `CFixedString::io_writer`, so encoders and `io::copy` can write straight
+/// into it.
+///
+/// A character can be split across two writes, so the start of one cut off
+/// at the end of a write is kept here until the next write completes it,
+/// and `flush` fails with `InvalidData` if one is still incomplete. Other
+/// invalid UTF-8 fails with `InvalidData` and zero bytes with
+/// `InvalidInput` whatever the `NulPolicy` is, as dropping or replacing
+/// part of a byte stream would corrupt it. A write that the heap budget
+/// cuts short reports how much was appended, and `write_all` fails with
+/// `WriteZero` once nothing more fits.
+///
+/// ```
+/// use cfixed_string::CFixedString;
+/// use std::io::{self, Write};
+///
+/// let mut t = CFixedString::new();
+/// let mut w = t.io_writer();
+///
+/// io::copy(&mut "så".as_bytes(), &mut w).unwrap();
+/// w.flush().unwrap();
+/// assert_eq!(t, "så");
+/// ```
+pub struct IoWriter<'a, const N: usize> {
+    s: &'a mut CFixedStringN<N>,
+    pending: [u8; 4],
+    pending_len: usize,
+}
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Reads a zero terminated string, such as one written by C code into
+    /// a pipe, a socket or a mapped file, consuming the terminator.
+    ///
+    /// Reads one byte at a time so nothing after the terminator is
+    /// consumed, wrap unbuffered readers in a `BufReader` for speed. More
+    /// than `max_len` bytes before the terminator, excluding it, fail with
+    /// `InvalidData`, as does invalid UTF-8. Running out of data first
+    /// fails with `UnexpectedEof`.
+    ///
+    /// This is not the counterpart of `write_to`, see `read_from` for that.
+    pub fn read_until_nul<R: Read>(mut r: R, max_len: usize) -> io::Result<Self> {
+        let mut inline = [0; N];
+        let mut heap = Vec::new();
+        let mut len = 0;
+        let mut byte = [0];
+
+        loop {
+            match r.read(&mut byte) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+
+            if byte[0] == 0 {
+                break;
+            }
+
+            if len == max_len {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    Error::CapacityExceeded { needed: len + 2 },
+                ));
+            }
+
+            if len < N {
+                inline[len] = byte[0];
+            } else {
+                if heap.is_empty() {
+                    heap.extend_from_slice(&inline);
+                }
+
+                heap.push(byte[0]);
+            }
+
+            len += 1;
+        }
+
+        let bytes = if len <= N { &inline[..len] } else { &heap[..] };
+
+        std::str::from_utf8(bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, Error::from(e)))?;
+
+        let mut s = Self::new();
+        s.push_bytes(bytes).map_err(io::Error::other)?;
+
+        Ok(s)
+    }
+}
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Returns an `io::Write` adapter that appends to the string, see
+    /// `IoWriter`
+    pub fn io_writer(&mut self) -> IoWriter<'_, N> {
+        IoWriter {
+            s: self,
+            pending: [0; 4],
+            pending_len: 0,
+        }
+    }
+}
+
+impl<'a, const N: usize> io::Write for IoWriter<'a, N> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        check_no_nul(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let offset = self.s.to_bytes().len();
+
+        // Completes the character left over from the last write first, the
+        // state is only updated once the write can't fail anymore
+        let mut pending = self.pending;
+        let mut pending_len = self.pending_len;
+        let mut taken = 0;
+
+        if pending_len > 0 {
+            taken = (utf8_width(pending[0]) - pending_len).min(buf.len());
+            pending[pending_len..pending_len + taken].copy_from_slice(&buf[..taken]);
+            pending_len += taken;
+        }
+
+        let head = match str::from_utf8(&pending[..pending_len]) {
+            Ok(head) => head,
+            Err(e) if e.error_len().is_none() => {
+                self.pending = pending;
+                self.pending_len = pending_len;
+                return Ok(taken);
+            }
+            Err(_) => {
+                self.pending_len = 0;
+                return Err(invalid_data(offset));
+            }
+        };
+
+        let rest = &buf[taken..];
+        let (valid, tail) = match str::from_utf8(rest) {
+            Ok(_) => (rest, &[][..]),
+            Err(e) if e.error_len().is_none() => rest.split_at(e.valid_up_to()),
+            Err(e) => return Err(invalid_data(offset + head.len() + e.valid_up_to())),
+        };
+
+        if !head.is_empty() {
+            self.s
+                .push_bytes(head.as_bytes())
+                .map_err(io::Error::other)?;
+
+            if self.s.to_bytes().len() == offset {
+                return Ok(0);
+            }
+
+            self.pending_len = 0;
+        }
+
+        let len = self.s.to_bytes().len();
+        self.s.push_bytes(valid).map_err(io::Error::other)?;
+
+        let appended = self.s.to_bytes().len() - len;
+        if appended < valid.len() {
+            return Ok(taken + appended);
+        }
+
+        self.pending[..tail.len()].copy_from_slice(tail);
+        self.pending_len = tail.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.pending_len > 0 {
+            self.pending_len = 0;
+            return Err(invalid_data(self.s.to_bytes().len()));
+        }
+
+        Ok(())
+    }
+}
+
+fn invalid_data(offset: usize) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, Error::InvalidUtf8 { offset })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CFixedString, CFixedStringN, Error, STRING_SIZE};
+    use std::io::{self, Write};
+
+    #[test]
+    fn test_io_write() {
+        let mut t = CFixedString::new();
+        let mut w = t.io_writer();
+        write!(w, "{}-{}", 1, 2).unwrap();
+        w.write_all(b" bytes").unwrap();
+
+        let long = "c".repeat(STRING_SIZE * 3);
+        io::copy(&mut long.as_bytes(), &mut w).unwrap();
+        w.flush().unwrap();
+        assert!(t.is_allocated());
+        assert!(t.to_string().starts_with("1-2 bytes"));
+        assert_eq!(t.to_bytes().len(), 9 + long.len());
+
+        let mut t = CFixedString::from_str("kept");
+        let err = t.io_writer().write_all(b"a\0b").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.into_inner().unwrap().downcast_ref::<Error>(),
+            Some(&Error::InteriorNul { position: 1 })
+        );
+        assert_eq!(t, "kept");
+    }
+
+    #[test]
+    fn test_io_write_utf8() {
+        let mut t = CFixedString::new();
+        let mut w = t.io_writer();
+
+        // "å😀" split inside both characters
+        let bytes = "å😀".as_bytes();
+        assert_eq!(w.write(&bytes[..1]).unwrap(), 1);
+        assert_eq!(w.write(&bytes[1..3]).unwrap(), 2);
+        assert_eq!(w.write(&bytes[3..4]).unwrap(), 1);
+        assert_eq!(w.write(&bytes[4..]).unwrap(), 2);
+        w.flush().unwrap();
+        assert_eq!(t, "å😀");
+
+        let mut w = t.io_writer();
+        let err = w.write(b"ok\xff\xfe").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.into_inner().unwrap().downcast_ref::<Error>(),
+            Some(&Error::InvalidUtf8 { offset: 8 })
+        );
+
+        // A continuation byte is missing
+        assert_eq!(w.write(b"\xc3").unwrap(), 1);
+        let err = w.write(b"a").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        assert_eq!(w.write(b"\xe2\x82").unwrap(), 2);
+        let err = w.flush().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(t, "å😀");
+        assert!(std::str::from_utf8(t.to_bytes()).is_ok());
+    }
+
+    #[test]
+    fn test_read_until_nul() {
+        let long = "r".repeat(STRING_SIZE * 2);
+        let data = format!("first\0{}\0tail", long);
+        let mut r = io::Cursor::new(data.as_bytes());
+
+        let t = CFixedString::read_until_nul(&mut r, 1024).unwrap();
+        assert_eq!(t, "first");
+
+        let t = CFixedStringN::<8>::read_until_nul(&mut r, long.len()).unwrap();
+        assert!(t.is_allocated());
+        assert_eq!(t, long);
+
+        let err = CFixedString::read_until_nul(&mut r, 1024).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let err = CFixedString::read_until_nul(&b"too long\0"[..], 3).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let err = CFixedString::read_until_nul(&b"\xff\0"[..], 3).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{CFixedStringN, Error};
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Creates a string from UTF-16 text, such as the output of Windows
+    /// wide APIs or a JNI `jchar*`.
+    ///
+    /// Fails on unpaired surrogates and zero code units, with the offset
+    /// counted in code units.
+    pub fn from_utf16(v: &[u16]) -> Result<Self, Error> {
+        let mut s = Self::new();
+        let mut offset = 0;
+
+        for c in char::decode_utf16(v.iter().copied()) {
+            let c = c.map_err(|_| Error::InvalidUtf16 { offset })?;
+
+            if c == '\0' {
+                return Err(Error::InteriorNul { position: offset });
+            }
+
+            s.push_bytes(c.encode_utf8(&mut [0; 4]).as_bytes())?;
+            offset += c.len_utf16();
+        }
+
+        Ok(s)
+    }
+
+    /// Creates a string from UTF-16 text, replacing unpaired surrogates
+    /// and zero code units with `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the text has to go on the heap and the heap budget
+    /// rejects it, like `From<&str>`.
+    pub fn from_utf16_lossy(v: &[u16]) -> Self {
+        Self::from_utf16_units_lossy(v.iter().copied())
+    }
+
+    fn from_utf16_units_lossy<I: IntoIterator<Item = u16>>(units: I) -> Self {
+        let mut s = Self::new();
+
+        for c in char::decode_utf16(units) {
+            // Zero is valid UTF-16 but can't be stored, whatever the
+            // `NulPolicy` is
+            let c = match c {
+                Ok('\0') | Err(_) => char::REPLACEMENT_CHARACTER,
+                Ok(c) => c,
+            };
+
+            s.push_bytes(c.encode_utf8(&mut [0; 4]).as_bytes()).unwrap();
+        }
+
+        s
+    }
+}
+
+#[cfg(windows)]
+impl<const N: usize> CFixedStringN<N> {
+    /// Creates a string from a zero terminated wide string as returned by
+    /// Windows APIs, measuring and converting it in a single pass.
+    ///
+    /// Unlike `OsStringExt::from_wide` the result is UTF-8, so unpaired
+    /// surrogates are replaced with `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same case as `from_utf16_lossy`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be non-null and point to a zero terminated sequence of
+    /// `u16`s.
+    pub unsafe fn from_wide(ptr: *const u16) -> Self {
+        let units = (0..).map(|i| *ptr.add(i)).take_while(|&c| c != 0);
+        Self::from_utf16_units_lossy(units)
+    }
+
+    /// Creates a string from a wide string of `len` code units, like
+    /// `from_wide` but without looking for a terminator. Zero code units
+    /// are replaced like in `from_utf16_lossy`.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same case as `from_utf16_lossy`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be non-null and valid for reads of `len` `u16`s.
+    pub unsafe fn from_wide_len(ptr: *const u16, len: usize) -> Self {
+        Self::from_utf16_lossy(std::slice::from_raw_parts(ptr, len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CFixedString;
+
+    #[test]
+    fn test_from_utf16() {
+        let wide: Vec<u16> = "wide 𝄞 text".encode_utf16().collect();
+        let t = CFixedString::from_utf16(&wide).unwrap();
+
+        assert!(!t.is_allocated());
+        assert_eq!(&t.to_string(), "wide 𝄞 text");
+
+        let bad = [b'a' as u16, 0xd834, b'b' as u16];
+        assert_eq!(
+            CFixedString::from_utf16(&bad).err(),
+            Some(Error::InvalidUtf16 { offset: 1 })
+        );
+        assert_eq!(
+            &CFixedString::from_utf16_lossy(&bad).to_string(),
+            "a\u{fffd}b"
+        );
+
+        assert_eq!(
+            CFixedString::from_utf16(&[b'a' as u16, 0]).err(),
+            Some(Error::InteriorNul { position: 1 })
+        );
+        assert_eq!(CFixedString::from_utf16_lossy(&[97, 0, 98]), "a\u{fffd}b");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_from_wide() {
+        let wide: Vec<u16> = "wide\0ignored".encode_utf16().collect();
+
+        unsafe {
+            assert_eq!(&CFixedString::from_wide(wide.as_ptr()).to_string(), "wide");
+            assert_eq!(
+                &CFixedString::from_wide_len(wide.as_ptr(), 3).to_string(),
+                "wid"
+            );
+            assert_eq!(
+                &CFixedString::from_wide_len(wide.as_ptr(), 6).to_string(),
+                "wide\u{fffd}i"
+            );
+        }
+    }
+}
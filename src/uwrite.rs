@@ -0,0 +1,62 @@
+use crate::{CFixedStringN, CFixedStringNoAlloc, Error};
+use ufmt::{uDisplay, uWrite, Formatter};
+
+/// Appends like `fmt::Write`, but reports the `Error` that stopped the
+/// write instead of `fmt::Error`.
+impl<const N: usize> uWrite for CFixedStringN<N> {
+    type Error = Error;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Error> {
+        self.push_bytes(s.as_bytes())
+    }
+}
+
+/// Uses `push_str`, so a write that doesn't fit fails and leaves the string
+/// unchanged.
+impl<const N: usize> uWrite for CFixedStringNoAlloc<N> {
+    type Error = Error;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Error> {
+        self.push_str(s)
+    }
+}
+
+/// Writes the contents like `to_string` does, with invalid UTF-8 replaced
+impl<const N: usize> uDisplay for CFixedStringN<N> {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.write_str(&self.to_string())
+    }
+}
+
+impl<const N: usize> uDisplay for CFixedStringNoAlloc<N> {
+    fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CFixedString, CFixedStringNoAlloc, Error};
+    use ufmt::uwrite;
+
+    #[test]
+    fn test_uwrite() {
+        let name = CFixedString::from_str("sensor");
+        let mut t = CFixedStringNoAlloc::<16>::new();
+        uwrite!(t, "{}={}", name, 42u16).unwrap();
+        assert_eq!(t.as_str(), "sensor=42");
+
+        assert_eq!(
+            uwrite!(t, "{}", "too long"),
+            Err(Error::CapacityExceeded { needed: 18 })
+        );
+
+        let mut heap = CFixedString::new();
+        uwrite!(heap, "[{}] {}", t, -1i32).unwrap();
+        assert_eq!(heap, "[sensor=42] -1");
+        assert_eq!(
+            uwrite!(heap, "{}", "a\0b"),
+            Err(Error::InteriorNul { position: 1 })
+        );
+    }
+}
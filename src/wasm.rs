@@ -0,0 +1,128 @@
+// Helpers for passing strings between a WebAssembly host and a guest
+// module that uses C-ABI strings in its linear memory.
+use crate::{CFixedStringN, Error};
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Reads a string from a guest's linear memory, given as the `offset`
+    /// and `len` in bytes the guest passed to the host.
+    ///
+    /// The range is bounds-checked against `memory` and the contents must
+    /// not contain zero bytes and must be valid UTF-8.
+    pub fn from_guest_memory(memory: &[u8], offset: u32, len: u32) -> Result<Self, Error> {
+        let start = offset as usize;
+        let bytes = start
+            .checked_add(len as usize)
+            .and_then(|end| memory.get(start..end))
+            .ok_or(Error::OutOfBounds {
+                offset: start,
+                len: len as usize,
+            })?;
+
+        from_validated(bytes)
+    }
+
+    /// Reads a zero terminated string from a guest's linear memory at
+    /// `offset`.
+    pub fn from_guest_cstr(memory: &[u8], offset: u32) -> Result<Self, Error> {
+        let start = offset as usize;
+        let tail = memory.get(start..).ok_or(Error::OutOfBounds {
+            offset: start,
+            len: 0,
+        })?;
+
+        let len = tail
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or(Error::OutOfBounds {
+                offset: start,
+                len: tail.len(),
+            })?;
+
+        from_validated(&tail[..len])
+    }
+
+    /// Copies the string with its terminator into a guest's linear memory at
+    /// `offset`, returning the length without the terminator.
+    pub fn write_to_guest(&self, memory: &mut [u8], offset: u32) -> Result<u32, Error> {
+        let bytes = self.to_bytes_with_nul();
+        let start = offset as usize;
+
+        let dst = start
+            .checked_add(bytes.len())
+            .and_then(|end| memory.get_mut(start..end))
+            .ok_or(Error::OutOfBounds {
+                offset: start,
+                len: bytes.len(),
+            })?;
+
+        dst.copy_from_slice(bytes);
+        Ok((bytes.len() - 1) as u32)
+    }
+
+    /// Returns the `(offset, len)` of the string in this module's linear
+    /// memory, for handing it to the host. The terminator follows at
+    /// `offset + len`.
+    ///
+    /// The pair is only valid while `self` is alive and not modified.
+    #[cfg(target_arch = "wasm32")]
+    pub fn as_wasm_parts(&self) -> (u32, u32) {
+        (self.as_ptr() as usize as u32, self.to_bytes().len() as u32)
+    }
+
+    /// Copies a string out of this module's linear memory, such as one the
+    /// host wrote there with `write_to_guest`.
+    ///
+    /// # Safety
+    ///
+    /// `offset` to `offset + len` must be a readable range of this module's
+    /// memory.
+    #[cfg(target_arch = "wasm32")]
+    pub unsafe fn from_wasm_parts(offset: u32, len: u32) -> Result<Self, Error> {
+        let bytes = std::slice::from_raw_parts(offset as usize as *const u8, len as usize);
+        from_validated(bytes)
+    }
+}
+
+fn from_validated<const N: usize>(bytes: &[u8]) -> Result<CFixedStringN<N>, Error> {
+    if let Some(position) = bytes.iter().position(|&b| b == 0) {
+        return Err(Error::InteriorNul { position });
+    }
+
+    std::str::from_utf8(bytes)?;
+
+    let mut s = CFixedStringN::new();
+    s.push_bytes(bytes)?;
+    Ok(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CFixedString;
+
+    #[test]
+    fn test_guest_memory() {
+        let mut memory = vec![0xaa; 64];
+
+        let t = CFixedString::from_str("guest");
+        assert_eq!(t.write_to_guest(&mut memory, 8), Ok(5));
+        assert_eq!(&memory[8..14], b"guest\0");
+
+        let t = CFixedString::from_guest_memory(&memory, 8, 5).unwrap();
+        assert_eq!(&t.to_string(), "guest");
+
+        let t = CFixedString::from_guest_cstr(&memory, 10).unwrap();
+        assert_eq!(&t.to_string(), "est");
+
+        assert_eq!(
+            CFixedString::from_guest_memory(&memory, 60, 5).err(),
+            Some(Error::OutOfBounds { offset: 60, len: 5 })
+        );
+        assert_eq!(
+            CFixedString::from_guest_memory(&memory, 8, 6).err(),
+            Some(Error::InteriorNul { position: 5 })
+        );
+        assert!(CFixedString::from_guest_cstr(&memory, 20).is_err());
+        assert!(t.write_to_guest(&mut memory, 62).is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use crate::{CFixedStringN, Error};
+use unicode_width::UnicodeWidthChar;
+
+// Widths are summed per character, which is what most terminals and text
+// grids do when advancing the cursor.
+fn char_width(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Returns the number of display columns the string takes, with CJK
+    /// and emoji counting as two and combining marks as zero.
+    pub fn width(&self) -> usize {
+        self.to_string().chars().map(char_width).sum()
+    }
+
+    /// Shortens the string to at most `max_columns` display columns without
+    /// splitting a character.
+    ///
+    /// Use this instead of `truncate_to_char_boundary` when the limit is a
+    /// column count in a terminal or C text grid rather than a byte count.
+    pub fn truncate_to_width(&mut self, max_columns: usize) {
+        let mut columns = 0;
+        let mut end = None;
+
+        for (index, c) in self.to_string().char_indices() {
+            columns += char_width(c);
+
+            if columns > max_columns {
+                end = Some(index);
+                break;
+            }
+        }
+
+        if let Some(end) = end {
+            self.truncate_bytes(end);
+        }
+    }
+
+    /// Appends spaces until the string takes at least `columns` display
+    /// columns, for aligning table cells.
+    pub fn pad_to_width(&mut self, columns: usize) -> Result<(), Error> {
+        const SPACES: &[u8] = &[b' '; 32];
+
+        let mut missing = columns.saturating_sub(self.width());
+
+        while missing > 0 {
+            let n = missing.min(SPACES.len());
+            self.push_bytes(&SPACES[..n])?;
+            missing -= n;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CFixedString;
+
+    #[test]
+    fn test_width() {
+        let mut t = CFixedString::from_str("日本語ab");
+        assert_eq!(t.width(), 8);
+
+        // Cutting in the middle of a wide character drops all of it
+        t.truncate_to_width(5);
+        assert_eq!(&t.to_string(), "日本");
+
+        t.pad_to_width(7).unwrap();
+        assert_eq!(&t.to_string(), "日本   ");
+        assert_eq!(t.width(), 7);
+
+        let mut t = CFixedString::from_str("e\u{301}x");
+        assert_eq!(t.width(), 2);
+        t.truncate_to_width(1);
+        assert_eq!(&t.to_string(), "e\u{301}");
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{CFixedStringN, Error};
+use std::fmt;
+
+/// A `fmt::Write` adapter that keeps the `Error` that stopped a write,
+/// returned by `CFixedString::try_writer`.
+///
+/// `fmt::Write` can only report `fmt::Error`, so code that formats through
+/// it can ask the adapter afterwards whether a zero byte or the heap budget
+/// was the cause. Once a write fails, later ones fail too, so the string
+/// never ends up with pieces missing from the middle.
+///
+/// ```
+/// use cfixed_string::{CFixedString, Error};
+/// use std::fmt::Write;
+///
+/// let (id, name) = (7, "a\0b");
+/// let mut t = CFixedString::new();
+/// let mut w = t.try_writer();
+///
+/// assert!(write!(w, "id={}, name={}", id, name).is_err());
+/// assert_eq!(w.finish(), Err(Error::InteriorNul { position: 1 }));
+/// assert_eq!(t.to_bytes(), b"id=7, name=");
+/// ```
+pub struct TryWriter<'a, const N: usize> {
+    s: &'a mut CFixedStringN<N>,
+    error: Option<Error>,
+}
+
+impl<const N: usize> CFixedStringN<N> {
+    /// Returns a `fmt::Write` adapter that keeps the error that stopped a
+    /// write, see `TryWriter`
+    pub fn try_writer(&mut self) -> TryWriter<'_, N> {
+        TryWriter {
+            s: self,
+            error: None,
+        }
+    }
+
+    /// Appends `s`, failing instead of panicking on zero bytes rejected by
+    /// `NulPolicy` or a heap budget that is exhausted
+    pub fn try_push_str(&mut self, s: &str) -> Result<(), Error> {
+        self.push_bytes(s.as_bytes())
+    }
+
+    /// Same as `try_push_str`, named after the `fmt::Write::write_str` it
+    /// stands in for
+    pub fn try_write_str(&mut self, s: &str) -> Result<(), Error> {
+        self.try_push_str(s)
+    }
+
+    /// Formats `args` into the string, keeping the error that stopped it
+    /// instead of collapsing it into `fmt::Error`
+    pub fn try_write_fmt(&mut self, args: fmt::Arguments<'_>) -> Result<(), Error> {
+        // Constant strings passed every frame skip the formatting machinery
+        // and become a single copy
+        if let Some(s) = args.as_str() {
+            return self.push_bytes(s.as_bytes());
+        }
+
+        let mut w = self.try_writer();
+
+        match fmt::write(&mut w, args) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(w.error.unwrap_or(Error::Format)),
+        }
+    }
+}
+
+impl<'a, const N: usize> TryWriter<'a, N> {
+    /// Returns the error that stopped the writes, if any
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+
+    /// Returns the error that stopped the writes, or `Ok` if there was none
+    pub fn finish(self) -> Result<(), Error> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a, const N: usize> fmt::Write for TryWriter<'a, N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.error.is_some() {
+            return Err(fmt::Error);
+        }
+
+        self.s.push_bytes(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CFixedString, Error};
+    use std::fmt::Write;
+
+    #[test]
+    fn test_try_writes() {
+        let mut t = CFixedString::new();
+        t.try_push_str("a").unwrap();
+        t.try_write_fmt(format_args!("{}{}", 'b', 3)).unwrap();
+        assert_eq!(
+            t.try_push_str("\0"),
+            Err(Error::InteriorNul { position: 0 })
+        );
+        assert_eq!(
+            t.try_write_str("c\0"),
+            Err(Error::InteriorNul { position: 1 })
+        );
+
+        let mut w = t.try_writer();
+        write!(w, "-{}", 4).unwrap();
+        assert!(w.error().is_none());
+        assert_eq!(w.finish(), Ok(()));
+        assert_eq!(t.to_bytes(), b"ab3-4");
+
+        // Stops at the first failure and reports it
+        let mut w = t.try_writer();
+        assert!(w.write_str("x\0").is_err());
+        assert!(w.write_str("y").is_err());
+        assert_eq!(w.error(), Some(&Error::InteriorNul { position: 1 }));
+        assert_eq!(t.to_bytes(), b"ab3-4");
+    }
+}
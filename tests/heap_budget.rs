@@ -0,0 +1,48 @@
+use cfixed_string::{heap_usage, set_heap_budget, BudgetPolicy, CFixedString};
+use std::fmt::Write;
+
+// The budget is process wide, so everything touching it lives in this one
+// test to keep the steps from racing each other.
+#[test]
+fn test_heap_budget() {
+    // The inline size depends on the build configuration
+    let cap = CFixedString::INLINE_CAPACITY;
+    let long = "x".repeat(cap * 2);
+    let base = heap_usage();
+
+    // The whole heap buffer counts, including the room left by doubling
+    let t = CFixedString::from_str(&long);
+    assert_eq!(heap_usage(), base + t.capacity() + 1);
+
+    drop(t);
+    assert_eq!(heap_usage(), base);
+
+    set_heap_budget(Some(base + cap + cap / 2), BudgetPolicy::Error);
+
+    let mut t = CFixedString::from_str("short");
+    assert!(t.write_str(&long).is_err());
+    assert_eq!(&t.to_string(), "short");
+
+    set_heap_budget(Some(base), BudgetPolicy::Truncate);
+
+    // 'é' is two bytes and straddles the end of the inline buffer
+    let mut t = CFixedString::from_str("y".repeat(cap - 11));
+    t.write_str("0123456789é-").unwrap();
+    assert!(!t.is_allocated());
+    assert_eq!(t.to_bytes().len(), cap - 1);
+
+    set_heap_budget(Some(base + cap + cap / 2), BudgetPolicy::Truncate);
+
+    let short_heap = "z".repeat(cap + 1);
+    let mut t = CFixedString::from_str(&short_heap);
+    assert_eq!(heap_usage(), base + short_heap.len() + 1);
+    t.write_str(&long).unwrap();
+    assert_eq!(t.to_bytes().len(), short_heap.len());
+
+    set_heap_budget(None, BudgetPolicy::Allow);
+    t.write_str(&long).unwrap();
+    assert_eq!(heap_usage(), base + short_heap.len() + long.len() + 1);
+
+    drop(t);
+    assert_eq!(heap_usage(), base);
+}
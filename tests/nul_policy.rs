@@ -0,0 +1,88 @@
+use cfixed_string::{
+    nul_policy, set_nul_policy, CFixedString, CFixedStringN, CFixedStringNoAlloc, CStorageString,
+    CStrBuf, Error, Global, NulPolicy,
+};
+use std::fmt::Write;
+
+// The policy is process wide, so everything touching it lives in this one
+// test to keep the steps from racing each other.
+#[test]
+fn test_nul_policy() {
+    assert_eq!(nul_policy(), NulPolicy::Reject);
+
+    // Inline and heap strings behave the same
+    let long = "n".repeat(CFixedString::INLINE_CAPACITY * 2);
+
+    for start in ["short", &long] {
+        let mut t = CFixedString::from_str(start);
+        assert!(t.write_str("a\0b").is_err());
+        assert_eq!(t.to_bytes(), start.as_bytes());
+    }
+
+    let mut t = CFixedStringN::<8>::from_str("ab");
+    assert_eq!(
+        t.write_display(&"c\0d"),
+        Err(Error::InteriorNul { position: 1 })
+    );
+
+    // The fixed buffer types follow the same policy
+    let mut storage = [1; 8];
+    let mut buf = CStrBuf::new(&mut storage);
+    assert_eq!(
+        buf.push_str("ab\0c"),
+        Err(Error::InteriorNul { position: 2 })
+    );
+    assert_eq!(buf.as_str(), "ab");
+    assert!(CFixedStringNoAlloc::<8>::from_str("a\0").is_err());
+
+    set_nul_policy(NulPolicy::Truncate);
+
+    let mut t = CFixedString::from_str("ab");
+    assert_eq!(t.pad_to(6, 0), Err(Error::InteriorNul { position: 0 }));
+
+    let mut t = CFixedStringN::<8>::from_str("ab");
+    t.write_str("c\0d").unwrap();
+    t.write_str("-more-than-fits\0x").unwrap();
+    assert_eq!(t.to_bytes(), b"abc-more-than-fits");
+
+    let mut buf = CStrBuf::new(&mut storage);
+    buf.push_str("ab\0c").unwrap();
+    assert_eq!(buf.as_str(), "ab");
+
+    let mut t = CFixedStringNoAlloc::<4>::from_str("abc\0more").unwrap();
+    assert_eq!(t.as_str(), "abc");
+    assert_eq!(t.push_str("\0d"), Ok(()));
+
+    set_nul_policy(NulPolicy::Replace);
+
+    let t = CFixedString::from_str("a\0\0b");
+    assert_eq!(t.to_bytes(), "a\u{fffd}\u{fffd}b".as_bytes());
+
+    let mut t = CStorageString::new([0u8; 8]);
+    t.push_str("a\0b").unwrap();
+    assert_eq!(t.as_str(), "a\u{fffd}b");
+
+    // The replacements count against the capacity
+    assert_eq!(t.push_str("\0"), Err(Error::CapacityExceeded { needed: 9 }));
+    assert_eq!(t.as_str(), "a\u{fffd}b");
+
+    let mut buf = CStrBuf::new(&mut storage);
+    assert_eq!(
+        buf.push_str("ab\0\0"),
+        Err(Error::CapacityExceeded { needed: 9 })
+    );
+    assert_eq!(buf.as_str(), "ab\u{fffd}");
+
+    // Padding with zero bytes is rejected whatever the policy, a replaced
+    // one would overshoot the width
+    let mut t = CFixedString::from_str("ab");
+    assert_eq!(t.pad_to(6, 0), Err(Error::InteriorNul { position: 0 }));
+    assert_eq!(t.to_bytes(), b"ab");
+
+    let mut t = CFixedStringN::<4>::new_in(Global);
+    t.push_str("\0 on the heap").unwrap();
+    assert!(t.is_allocated());
+    assert_eq!(t.as_str(), "\u{fffd} on the heap");
+
+    set_nul_policy(NulPolicy::Reject);
+}